@@ -0,0 +1,78 @@
+use crate::component::{Block, Registry};
+use tokio::sync::mpsc;
+
+/// Cache of the latest block from every component, in the order of registration.
+pub struct Bar {
+    store: Vec<Option<Block>>,
+    received: Vec<bool>,
+}
+
+impl Bar {
+    pub fn new(size: usize) -> Self {
+        Self {
+            store: vec![None; size],
+            received: vec![false; size],
+        }
+    }
+
+    /// Store the new content of the component `id`. Return true if the content changed.
+    pub fn update(&mut self, id: usize, content: Option<Block>) -> bool {
+        self.received[id] = true;
+        if self.store[id] == content {
+            return false;
+        }
+        self.store[id] = content;
+        true
+    }
+
+    /// Return true when every component has sent its content at least once.
+    pub fn is_complete(&self) -> bool {
+        self.received.iter().all(|received| *received)
+    }
+
+    /// The visible blocks, from left to right.
+    pub fn blocks(&self) -> Vec<&Block> {
+        self.store.iter().flatten().collect()
+    }
+}
+
+/// Run every component in its own task, each component is updated on its own schedule.
+/// Return the bar to hold the contents, and the receiver of the `(id, content)` updates.
+pub fn schedule(registry: Registry) -> (Bar, mpsc::Receiver<(usize, Option<Block>)>) {
+    let components = registry.into_components();
+    let bar = Bar::new(components.len());
+    let (tx, rx) = mpsc::channel(components.len().max(1));
+
+    for (id, mut component) in components.into_iter().enumerate() {
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            loop {
+                let content = component.update().await;
+                if tx.send((id, content)).await.is_err() {
+                    tracing::info!("Stop updating component {}", component.name());
+                    break;
+                }
+                component.wait().await;
+            }
+        });
+    }
+
+    (bar, rx)
+}
+
+#[test]
+fn test_bar_update() {
+    let mut bar = Bar::new(3);
+
+    assert!(bar.update(0, Some(Block::new("A", "a"))));
+    assert!(!bar.update(0, Some(Block::new("A", "a"))));
+    assert!(!bar.update(1, None));
+    assert!(!bar.is_complete());
+    assert!(bar.update(2, Some(Block::new("C", "c"))));
+    assert!(bar.is_complete());
+
+    assert_eq!(
+        bar.blocks(),
+        vec![&Block::new("A", "a"), &Block::new("C", "c")]
+    );
+}
@@ -1,31 +1,685 @@
+use super::notification::{Notifier, Urgency};
 use super::widget::Block;
-use tokio::fs;
+use super::Component;
+use super::SysRoot;
+use crate::config::{BatteryConfig, BatteryMode, BatteryNotification, ColorConfig};
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Directory of all the power supplies, like AC adapters and batteries
+const POWER_SUPPLY: &str = "/sys/class/power_supply";
 
 /// Build a component to show laptop battery percentage and power-supply status.
-/// Statistic come from /sys/class/power_supply/<bat_name>/{capacity,status}.
+/// Every `/sys/class/power_supply/*` entry with type `Battery` under the [`SysRoot`] is
+/// discovered, batteries of peripheral devices like wireless mice are ignored.
 ///
-/// Return None if no battery device name contains "BAT0" keyword,
-/// or no capacity/status file was found.
-pub async fn battery() -> Option<Block> {
-    let perc = tokio::spawn(async {
-        fs::read_to_string(format!("/sys/class/power_supply/{}/capacity", "BAT0"))
-                .await
-                .ok()?
-                .parse::<i32>()
-                .ok()
-    });
-
-    let stat = fs::read_to_string(format!("/sys/class/power_supply/{}/status", "BAT0"))
-        .await
-        .ok()?;
-
-    let icon = if stat == "Discharging" { "" } else { "" };
-
-    let perc = perc.await.unwrap()?;
-
-    Some(
-        Block::new(icon, format!("{} %", perc))
-            .text_fg("#EAEAEA")
-            .icon_fg("#EAEAEA"),
-    )
+/// Return None if no battery was found.
+pub struct Battery {
+    root: SysRoot,
+    config: BatteryConfig,
+    /// Name of the battery to show, None to combine all of them
+    device: Option<String>,
+    estimator: Estimator,
+    /// Only set on the first block, the notifications are on all the batteries as a
+    /// whole even when they are shown separately
+    alarm: Option<Alarm>,
+}
+
+impl Battery {
+    /// Build the battery blocks for the configured mode: a single block for all the
+    /// batteries, or one block for each battery present now. Low battery notifications
+    /// are sent through the `notifier` if given, by the first block only.
+    pub async fn discover(
+        config: &BatteryConfig,
+        root: &SysRoot,
+        notifier: Option<Notifier>,
+    ) -> Vec<Self> {
+        let build = |device, first: bool| Self {
+            root: root.clone(),
+            config: config.clone(),
+            device,
+            estimator: Estimator::new(config.estimate_window),
+            alarm: first.then(|| Alarm::new(config, notifier.clone())),
+        };
+
+        match config.mode {
+            BatteryMode::Combined => vec![build(None, true)],
+            BatteryMode::Separate => {
+                let devices = batteries(root).await;
+                if devices.is_empty() {
+                    info!("No battery found");
+                }
+                devices
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, dev)| build(Some(dev), i == 0))
+                    .collect()
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Component for Battery {
+    fn name(&self) -> &'static str {
+        "battery"
+    }
+
+    fn interval(&self) -> Duration {
+        self.config.interval.or(30)
+    }
+
+    async fn update(&mut self) -> Option<Block> {
+        let summary = read_summary(&self.root, self.device.as_deref()).await;
+        if let Some(alarm) = self.alarm.as_mut() {
+            let all = match self.device {
+                Some(_) => read_summary(&self.root, None).await,
+                None => summary.clone(),
+            };
+            if let Some(all) = all {
+                alarm.update(&all).await;
+            }
+        }
+
+        let summary = summary?;
+        let time = self.estimator.estimate(&summary);
+        Some(battery_block(
+            summary.perc,
+            summary.status,
+            time,
+            &self.config,
+        ))
+    }
+}
+
+/// Read the battery with the given name, or all the batteries as a whole.
+async fn read_summary(root: &SysRoot, device: Option<&str>) -> Option<Summary> {
+    let devices = match device {
+        Some(device) => vec![device.to_string()],
+        None => batteries(root).await,
+    };
+
+    let mut stats = Vec::with_capacity(devices.len());
+    for device in devices {
+        // the battery may be removed after discovered
+        if let Some(stat) = BatteryStat::read(root, &device).await {
+            stats.push(stat);
+        }
+    }
+
+    let mut summary = combine(&stats)?;
+    summary.status = summary.status.plugged(ac_online(root).await);
+    Some(summary)
+}
+
+/// Estimate the time to empty when discharging, or the time to full when charging.
+struct Estimator {
+    /// Recent charging or discharging rates to smooth the remaining time
+    rates: RollingAverage,
+    /// Status of the last estimate, the rates are cleared when it changes
+    status: Option<Status>,
+}
+
+impl Estimator {
+    fn new(window: usize) -> Self {
+        Self {
+            rates: RollingAverage::new(window),
+            status: None,
+        }
+    }
+
+    fn estimate(&mut self, summary: &Summary) -> Option<Duration> {
+        if self.status != Some(summary.status) {
+            self.rates.clear();
+            self.status = Some(summary.status);
+        }
+
+        let energy = summary.energy.as_ref()?;
+        let remaining = match summary.status {
+            Status::Discharging => energy.now,
+            Status::Charging => energy.full.saturating_sub(energy.now),
+            _ => return None,
+        };
+
+        self.rates.push(energy.rate?);
+        match self.rates.average()? {
+            0 => None,
+            // µWh / µW or µAh / µA, both are in hours
+            rate => Some(Duration::from_secs(remaining * 3600 / rate)),
+        }
+    }
+}
+
+/// Send desktop notifications when the battery is low. Each level is notified once
+/// for a discharge, the alarm is reset when the battery is not discharging.
+struct Alarm {
+    config: BatteryNotification,
+    notifier: Option<Notifier>,
+    /// Remaining time of all the batteries, which may differ from the shown block
+    estimator: Estimator,
+    /// The most urgent level notified in this discharge
+    fired: Option<Urgency>,
+    /// Id of the last notification, replaced by the next one
+    id: u32,
+}
+
+impl Alarm {
+    fn new(config: &BatteryConfig, notifier: Option<Notifier>) -> Self {
+        Self {
+            config: config.notification.clone(),
+            notifier,
+            estimator: Estimator::new(config.estimate_window),
+            fired: None,
+            id: 0,
+        }
+    }
+
+    /// Return the level to notify, if it's not notified yet.
+    fn check(&mut self, perc: i32, status: Status) -> Option<Urgency> {
+        if status != Status::Discharging {
+            self.fired = None;
+            return None;
+        }
+
+        let level = if perc <= self.config.critical {
+            Urgency::Critical
+        } else if perc <= self.config.low {
+            Urgency::Normal
+        } else {
+            return None;
+        };
+        if self.fired >= Some(level) {
+            return None;
+        }
+        self.fired = Some(level);
+        Some(level)
+    }
+
+    async fn update(&mut self, summary: &Summary) {
+        let time = self.estimator.estimate(summary);
+        let level = match self.check(summary.perc, summary.status) {
+            Some(level) => level,
+            None => return,
+        };
+        let notifier = match &self.notifier {
+            Some(notifier) => notifier,
+            None => return,
+        };
+
+        let (icon, title, body) = alarm_text(level, summary.perc, time);
+        match notifier.notify(self.id, icon, title, &body, level).await {
+            Ok(id) => self.id = id,
+            Err(e) => warn!("Fail to send the low battery notification: {}", e),
+        }
+    }
+}
+
+/// Icon, title and body of the low battery notification.
+fn alarm_text(
+    level: Urgency,
+    perc: i32,
+    time: Option<Duration>,
+) -> (&'static str, &'static str, String) {
+    let (icon, title) = match level {
+        Urgency::Critical => ("battery-caution", "Battery critically low"),
+        _ => ("battery-low", "Battery low"),
+    };
+    let mut body = format!("{} % remaining", perc);
+    if let Some(time) = time {
+        body += &format!(", {} left", format_time(time));
+    }
+    (icon, title, body)
+}
+
+/// Average of the latest sample is dropped when the window is full.
+struct RollingAverage {
+    samples: VecDeque<u64>,
+    size: usize,
+}
+
+impl RollingAverage {
+    fn new(size: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(size),
+            size,
+        }
+    }
+
+    fn push(&mut self, sample: u64) {
+        if self.size == 0 {
+            return;
+        }
+        if self.samples.len() == self.size {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    fn average(&self) -> Option<u64> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        Some(self.samples.iter().sum::<u64>() / self.samples.len() as u64)
+    }
+}
+
+/// Names of the system batteries, sorted.
+async fn batteries(root: &SysRoot) -> Vec<String> {
+    let mut batteries = Vec::new();
+    for name in root.list(POWER_SUPPLY).await {
+        let dir = format!("{}/{}", POWER_SUPPLY, name);
+        let kind = root.read::<String, _>(format!("{}/type", dir)).await;
+        // peripheral devices report their battery with the `Device` scope
+        let scope = root.read::<String, _>(format!("{}/scope", dir)).await;
+        if kind.as_deref() == Some("Battery") && scope.as_deref() != Some("Device") {
+            batteries.push(name);
+        }
+    }
+    batteries
+}
+
+/// Whether any AC adapter is plugged in, None if the machine has no adapter.
+async fn ac_online(root: &SysRoot) -> Option<bool> {
+    let mut online = None;
+    for name in root.list(POWER_SUPPLY).await {
+        let dir = format!("{}/{}", POWER_SUPPLY, name);
+        let kind = root.read::<String, _>(format!("{}/type", dir)).await;
+        if kind.as_deref() == Some("Battery") {
+            continue;
+        }
+        if let Some(state) = root.read::<u8, _>(format!("{}/online", dir)).await {
+            online = Some(online.unwrap_or(false) || state == 1);
+        }
+    }
+    online
+}
+
+/// Content of the power supply `status` file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Status {
+    Charging,
+    Discharging,
+    Full,
+    NotCharging,
+    Unknown,
+}
+
+impl FromStr for Status {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.trim() {
+            "Charging" => Self::Charging,
+            "Discharging" => Self::Discharging,
+            "Full" => Self::Full,
+            "Not charging" => Self::NotCharging,
+            _ => Self::Unknown,
+        })
+    }
+}
+
+impl Status {
+    /// Correct the status with the AC adapter state. Some laptops report `Not charging`
+    /// or `Unknown` when the charge threshold is reached, or when idle on battery.
+    fn plugged(self, ac_online: Option<bool>) -> Self {
+        match (self, ac_online) {
+            (Self::NotCharging | Self::Unknown, Some(false)) => Self::Discharging,
+            (Self::Unknown, Some(true)) => Self::NotCharging,
+            (status, _) => status,
+        }
+    }
+}
+
+struct BatteryStat {
+    capacity: i32,
+    status: Status,
+    energy: Option<Energy>,
+}
+
+/// Energy in µWh and power in µW, or charge in µAh and current in µA, depending on
+/// what the driver reports.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Energy {
+    now: u64,
+    full: u64,
+    /// Charging or discharging rate
+    rate: Option<u64>,
+}
+
+impl Energy {
+    const EMPTY: Self = Self {
+        now: 0,
+        full: 0,
+        rate: Some(0),
+    };
+}
+
+impl BatteryStat {
+    async fn read(root: &SysRoot, device: &str) -> Option<Self> {
+        let dir = format!("{}/{}", POWER_SUPPLY, device);
+        let attr = |name: &str| format!("{}/{}", dir, name);
+
+        let (capacity, status) = tokio::join!(
+            root.read::<i32, _>(attr("capacity")),
+            root.read::<Status, _>(attr("status")),
+        );
+
+        let mut energy = None;
+        for (now, full, rate) in [
+            ("energy_now", "energy_full", "power_now"),
+            ("charge_now", "charge_full", "current_now"),
+        ] {
+            if let (Some(now), Some(full), rate) = tokio::join!(
+                root.read::<u64, _>(attr(now)),
+                root.read::<u64, _>(attr(full)),
+                root.read::<u64, _>(attr(rate)),
+            ) {
+                energy = Some(Energy { now, full, rate });
+                break;
+            }
+        }
+
+        Some(Self {
+            capacity: capacity?,
+            status: status?,
+            energy,
+        })
+    }
+}
+
+/// All the batteries as a whole.
+#[derive(Clone)]
+struct Summary {
+    perc: i32,
+    status: Status,
+    energy: Option<Energy>,
+}
+
+/// Percentage and status of all the batteries. The percentage is weighted by the battery
+/// energy when all the batteries report it, otherwise it is the average capacity.
+fn combine(stats: &[BatteryStat]) -> Option<Summary> {
+    let first = stats.first()?;
+    if stats.len() == 1 {
+        return Some(Summary {
+            perc: first.capacity,
+            status: first.status,
+            energy: first.energy,
+        });
+    }
+
+    let energy = stats
+        .iter()
+        .map(|stat| stat.energy)
+        .try_fold(Energy::EMPTY, |sum, energy| {
+            let energy = energy?;
+            Some(Energy {
+                now: sum.now + energy.now,
+                full: sum.full + energy.full,
+                rate: sum.rate.zip(energy.rate).map(|(sum, rate)| sum + rate),
+            })
+        });
+    let perc = match energy {
+        Some(Energy { now, full, .. }) if full > 0 => {
+            (now as f64 * 100.0 / full as f64).round() as i32
+        }
+        _ => stats.iter().map(|stat| stat.capacity).sum::<i32>() / stats.len() as i32,
+    };
+
+    // any discharging battery means the laptop is running on battery
+    let status = [Status::Discharging, Status::Charging]
+        .into_iter()
+        .find(|status| stats.iter().any(|stat| stat.status == *status))
+        .unwrap_or(first.status);
+
+    Some(Summary {
+        perc,
+        status,
+        energy,
+    })
+}
+
+/// Format as `H:MM`
+fn format_time(time: Duration) -> String {
+    let minutes = time.as_secs() / 60;
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
+
+pub(super) fn battery_block(
+    perc: i32,
+    status: Status,
+    time: Option<Duration>,
+    config: &BatteryConfig,
+) -> Block {
+    let (icon, icon_fg) = match status {
+        Status::Charging => ("", config.status_color.charging),
+        Status::Discharging => ("", config.status_color.discharging),
+        Status::Full => ("", config.status_color.full),
+        Status::NotCharging => ("", config.status_color.not_charging),
+        Status::Unknown => ("", config.status_color.unknown),
+    };
+    let text = match time {
+        Some(time) => format!("{} % {}", perc, format_time(time)),
+        None => format!("{} %", perc),
+    };
+    Block::new(icon, text)
+        .color(&config.color)
+        .color(&ColorConfig {
+            icon_fg,
+            ..Default::default()
+        })
+        .threshold(perc as f64, &config.thresholds)
+}
+
+#[tokio::test]
+async fn test_battery_fixtures() {
+    use crate::config::UrgentColor;
+    use crate::output::status2d;
+
+    let config = BatteryConfig::default();
+    let fixture = |name: &str| Battery {
+        root: SysRoot::fixture(name),
+        config: config.clone(),
+        device: None,
+        estimator: Estimator::new(config.estimate_window),
+        alarm: Some(Alarm::new(&config, None)),
+    };
+
+    // the plain text and the status2d rendering with the status color of the icon
+    let render = |block: Option<Block>| {
+        let block = block.unwrap();
+        (block.to_string(), status2d(&block, &UrgentColor::default()))
+    };
+    let expect = |plain: &str, dwm: &str| (plain.to_string(), dwm.to_string());
+
+    assert_eq!(
+        render(fixture("battery/charging").update().await),
+        expect(" 57 %", "^c#A3BE8C^ ^c#EAEAEA^57 %")
+    );
+    assert_eq!(
+        render(fixture("battery/discharging").update().await),
+        expect(" 42 %", "^c#EAEAEA^ ^c#EAEAEA^42 %")
+    );
+    assert_eq!(
+        render(fixture("battery/full").update().await),
+        expect(" 100 %", "^c#88C0D0^ ^c#EAEAEA^100 %")
+    );
+    assert_eq!(
+        render(fixture("battery/not-charging").update().await),
+        expect(" 80 %", "^c#EBCB8B^ ^c#EAEAEA^80 %")
+    );
+    assert_eq!(fixture("battery/missing").update().await, None);
+}
+
+#[tokio::test]
+async fn test_multiple_batteries() {
+    let root = SysRoot::fixture("battery/dual");
+    assert_eq!(batteries(&root).await, vec!["BAT0", "BAT1"]);
+
+    let mut config = BatteryConfig::default();
+    assert_eq!(ac_online(&root).await, Some(false));
+    let mut combined = Battery::discover(&config, &root, None).await;
+    assert_eq!(combined.len(), 1);
+    assert_eq!(
+        combined[0].update().await,
+        Some(battery_block(
+            35,
+            Status::Discharging,
+            Some(Duration::from_secs(3 * 3600 + 30 * 60)),
+            &config
+        ))
+    );
+
+    config.mode = BatteryMode::Separate;
+    let mut separate = Battery::discover(&config, &root, None).await;
+    assert_eq!(separate.len(), 2);
+    assert_eq!(
+        separate[0].update().await,
+        Some(battery_block(
+            80,
+            Status::Discharging,
+            Some(Duration::from_secs(2 * 3600)),
+            &config
+        ))
+    );
+    assert_eq!(
+        separate[1].update().await,
+        // reported as unknown, but the AC adapter is unplugged
+        Some(battery_block(20, Status::Discharging, None, &config))
+    );
+
+    let missing = Battery::discover(&config, &SysRoot::fixture("battery/missing"), None).await;
+    assert!(missing.is_empty());
+}
+
+#[test]
+fn test_estimate_smoothing() {
+    let mut estimator = Estimator::new(3);
+    let mut estimate = |status, now, rate| {
+        let summary = Summary {
+            perc: 50,
+            status,
+            energy: Some(Energy {
+                now,
+                full: 40,
+                rate: Some(rate),
+            }),
+        };
+        estimator.estimate(&summary).map(|time| time.as_secs() / 60)
+    };
+
+    assert_eq!(estimate(Status::Discharging, 20, 10), Some(120));
+    // a spike of the power draw is averaged out
+    assert_eq!(estimate(Status::Discharging, 20, 40), Some(48));
+    assert_eq!(estimate(Status::Discharging, 20, 10), Some(60));
+    assert_eq!(estimate(Status::Discharging, 20, 10), Some(60));
+    assert_eq!(estimate(Status::Discharging, 20, 10), Some(120));
+    // the window is cleared when plugged in
+    assert_eq!(estimate(Status::Charging, 20, 20), Some(60));
+    assert_eq!(estimate(Status::Full, 40, 0), None);
+    assert_eq!(estimate(Status::Discharging, 40, 0), None);
+}
+
+#[test]
+fn test_alarm_levels() {
+    let mut alarm = Alarm::new(&BatteryConfig::default(), None);
+
+    assert_eq!(alarm.check(16, Status::Discharging), None);
+    assert_eq!(alarm.check(15, Status::Discharging), Some(Urgency::Normal));
+    assert_eq!(alarm.check(14, Status::Discharging), None);
+    // jitter around the threshold is not notified again
+    assert_eq!(alarm.check(16, Status::Discharging), None);
+    assert_eq!(alarm.check(15, Status::Discharging), None);
+    assert_eq!(alarm.check(5, Status::Discharging), Some(Urgency::Critical));
+    assert_eq!(alarm.check(4, Status::Discharging), None);
+
+    // notify again in the next discharge
+    assert_eq!(alarm.check(6, Status::Charging), None);
+    assert_eq!(alarm.check(3, Status::Discharging), Some(Urgency::Critical));
+}
+
+#[test]
+fn test_alarm_text() {
+    assert_eq!(
+        alarm_text(Urgency::Normal, 15, Some(Duration::from_secs(95 * 60))),
+        (
+            "battery-low",
+            "Battery low",
+            "15 % remaining, 1:35 left".to_string()
+        )
+    );
+    assert_eq!(
+        alarm_text(Urgency::Critical, 4, None),
+        (
+            "battery-caution",
+            "Battery critically low",
+            "4 % remaining".to_string()
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_separate_alarm() {
+    let root = SysRoot::fixture("battery/dual");
+    let mut config = BatteryConfig {
+        mode: BatteryMode::Separate,
+        ..BatteryConfig::default()
+    };
+    config.notification.low = 30;
+
+    let mut separate = Battery::discover(&config, &root, None).await;
+    assert!(separate[1].alarm.is_none());
+    for battery in &mut separate {
+        battery.update().await;
+    }
+    // BAT1 is at 20 %, but all the batteries are at 35 %
+    let alarm = separate[0].alarm.as_ref().unwrap();
+    assert_eq!(alarm.fired, None);
+
+    config.notification.low = 40;
+    let mut separate = Battery::discover(&config, &root, None).await;
+    separate[0].update().await;
+    let alarm = separate[0].alarm.as_ref().unwrap();
+    assert_eq!(alarm.fired, Some(Urgency::Normal));
+}
+
+#[tokio::test]
+#[ignore = "needs dbus-daemon"]
+async fn test_low_battery_notification() {
+    use super::bus::PrivateBus;
+    use super::notification::{stand_in, Received};
+    use futures::{FutureExt, StreamExt};
+
+    let bus = PrivateBus::start().expect("dbus-daemon is not installed");
+    let mut received = stand_in(bus.connect()).await;
+
+    let mut config = BatteryConfig::default();
+    config.notification.low = 50;
+    let root = SysRoot::fixture("battery/discharging");
+    let notifier = Some(Notifier::new(bus.connect()));
+    let mut battery = Battery::discover(&config, &root, notifier).await.remove(0);
+
+    battery.update().await;
+    battery.update().await;
+    battery.root = SysRoot::fixture("battery/charging");
+    battery.update().await;
+    battery.root = root;
+    battery.update().await;
+
+    let low = |replaces| Received {
+        replaces,
+        summary: "Battery low".to_string(),
+        body: "42 % remaining".to_string(),
+        urgency: 1,
+    };
+    assert_eq!(received.next().await, Some(low(0)));
+    assert_eq!(received.next().await, Some(low(1)));
+    // nothing else is queued
+    assert!(received.next().now_or_never().is_none());
 }
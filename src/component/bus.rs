@@ -0,0 +1,87 @@
+use anyhow::Result;
+use dbus::nonblock::SyncConnection;
+use dbus_tokio::connection::{self, IOResource};
+use std::sync::Arc;
+use tracing::{error, info};
+
+/// Session bus connection shared by the components, connected on the first use.
+#[derive(Default)]
+pub struct Session(Option<Arc<SyncConnection>>);
+
+impl Session {
+    pub fn get(&mut self) -> Result<Arc<SyncConnection>> {
+        if let Some(conn) = &self.0 {
+            return Ok(conn.clone());
+        }
+
+        let (resource, conn) = connection::new_session_sync()?;
+        hold(resource, "session");
+        self.0 = Some(conn.clone());
+        Ok(conn)
+    }
+}
+
+/// Connect to the system bus, for the system services like NetworkManager.
+pub fn system_bus() -> Result<Arc<SyncConnection>> {
+    let (resource, conn) = connection::new_system_sync()?;
+    hold(resource, "system");
+    Ok(conn)
+}
+
+fn hold(resource: IOResource<SyncConnection>, bus: &'static str) {
+    tokio::spawn(async move {
+        info!("Holding connection to D-Bus {} bus", bus);
+        let err = resource.await;
+        error!("Lost connection to D-Bus {} bus: {}", bus, err);
+    });
+}
+
+/// A dbus-daemon only for tests, killed when dropped.
+#[cfg(test)]
+pub struct PrivateBus {
+    daemon: std::process::Child,
+    address: String,
+}
+
+#[cfg(test)]
+impl PrivateBus {
+    /// Return None if dbus-daemon is not installed.
+    pub fn start() -> Option<Self> {
+        use std::io::{BufRead, BufReader};
+        use std::process::{Command, Stdio};
+
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take()?)
+            .read_line(&mut address)
+            .ok()?;
+
+        Some(Self {
+            daemon,
+            address: address.trim().to_string(),
+        })
+    }
+
+    pub fn connect(&self) -> Arc<SyncConnection> {
+        let mut channel = dbus::channel::Channel::open_private(&self.address).unwrap();
+        channel.register().unwrap();
+        let (resource, conn) = connection::from_channel::<SyncConnection>(channel).unwrap();
+        tokio::spawn(async {
+            resource.await;
+        });
+        conn
+    }
+}
+
+#[cfg(test)]
+impl Drop for PrivateBus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
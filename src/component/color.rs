@@ -1,59 +1,158 @@
+use anyhow::{bail, Error};
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// A color parsed from hex code `#RRGGBB` or `#RRGGBBAA`. Invalid hex code is rejected
+/// when parsing, so every output backend can render it in its own escape syntax.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Rgba {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Rgba {
+    /// Opaque color
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b, a: 0xFF }
+    }
+
+    pub const fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Hex code in `#RRGGBB` format, the alpha channel is dropped.
+    pub fn rgb_hex(&self) -> String {
+        format!("#{:02X}{:02X}{:02X}", self.r, self.g, self.b)
+    }
+}
+
+impl FromStr for Rgba {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = match s.strip_prefix('#') {
+            Some(hex) if (hex.len() == 6 || hex.len() == 8) && hex.is_ascii() => hex,
+            _ => bail!(
+                "invalid color `{}`, expected a hex code like `#RRGGBB` or `#RRGGBBAA`",
+                s
+            ),
+        };
+
+        let channel = |i: usize| {
+            u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| {
+                anyhow::anyhow!(
+                    "invalid color `{}`, `{}` is not a hex number",
+                    s,
+                    &hex[i..i + 2]
+                )
+            })
+        };
+
+        let a = if hex.len() == 8 { channel(6)? } else { 0xFF };
+        Ok(Self::new(channel(0)?, channel(2)?, channel(4)?, a))
+    }
+}
+
+impl TryFrom<String> for Rgba {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for Rgba {
+    /// Format as `#RRGGBB`, or `#RRGGBBAA` if the color is not opaque.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.rgb_hex())?;
+        if self.a != 0xFF {
+            write!(f, "{:02X}", self.a)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, PartialEq, Default, Clone)]
 pub struct Color {
-    pub fg: Option<String>,
-    pub bg: Option<String>,
-    pub icon_fg: Option<String>,
-    pub icon_bg: Option<String>,
+    pub fg: Option<Rgba>,
+    pub bg: Option<Rgba>,
+    pub icon_fg: Option<Rgba>,
+    pub icon_bg: Option<Rgba>,
 }
 
 impl Color {
-    /// Create a new color set. The first parameter `fg` means foreground, and the second
-    /// parameter `bg` means background. You should give a valid hex color code in format
-    /// like `#FFFFFF`. If you pass empty string, this means you are going to use the
-    /// default colors.
+    /// Create a new color set. Unset colors mean using the default colors of the
+    /// output.
     pub fn new() -> Self {
         Self::default()
     }
 
-    pub fn text<T: Into<String>, E: Into<String>>(mut self, fg: T, bg: E) -> Self {
-        self.fg = Some(format!("^c{}^", fg.into()));
-        self.bg = Some(format!("^b{}^", bg.into()));
+    #[allow(dead_code)]
+    pub fn text(mut self, fg: Rgba, bg: Rgba) -> Self {
+        self.fg = Some(fg);
+        self.bg = Some(bg);
         self
     }
 
-    pub fn icon<T: Into<String>, E: Into<String>>(mut self, fg: T, bg: E) -> Self {
-        self.icon_fg = Some(format!("^c{}^", fg.into()));
-        self.icon_bg = Some(format!("^b{}^", bg.into()));
+    #[allow(dead_code)]
+    pub fn icon(mut self, fg: Rgba, bg: Rgba) -> Self {
+        self.icon_fg = Some(fg);
+        self.icon_bg = Some(bg);
         self
     }
 
-    pub fn text_fg<T: Into<String>>(mut self, fg: T) -> Self {
-        self.fg = Some(format!("^c{}^", fg.into()));
+    pub fn text_fg(mut self, fg: Rgba) -> Self {
+        self.fg = Some(fg);
         self
     }
 
-    #[allow(dead_code)]
-    pub fn text_bg<T: Into<String>>(mut self, bg: T) -> Self {
-        self.bg = Some(format!("^b{}^", bg.into()));
+    pub fn text_bg(mut self, bg: Rgba) -> Self {
+        self.bg = Some(bg);
         self
     }
 
-    #[allow(dead_code)]
-    pub fn icon_fg<T: Into<String>>(mut self, fg: T) -> Self {
-        self.icon_fg = Some(format!("^c{}^", fg.into()));
+    pub fn icon_fg(mut self, fg: Rgba) -> Self {
+        self.icon_fg = Some(fg);
         self
     }
 
-    #[allow(dead_code)]
-    pub fn icon_bg<T: Into<String>>(mut self, bg: T) -> Self {
-        self.icon_bg = Some(format!("^b{}^", bg.into()));
+    pub fn icon_bg(mut self, bg: Rgba) -> Self {
+        self.icon_bg = Some(bg);
         self
     }
 }
 
+#[test]
+fn test_rgba_parse() {
+    assert_eq!(
+        "#EAEAEA".parse::<Rgba>().unwrap(),
+        Rgba::rgb(0xEA, 0xEA, 0xEA)
+    );
+    assert_eq!(
+        "#0c0c0c80".parse::<Rgba>().unwrap(),
+        Rgba::new(0x0C, 0x0C, 0x0C, 0x80)
+    );
+    assert!("#EAEAE".parse::<Rgba>().is_err());
+    assert!("EAEAEA".parse::<Rgba>().is_err());
+    assert!("#GGGGGG".parse::<Rgba>().is_err());
+    assert!("#ÉAEAE".parse::<Rgba>().is_err());
+
+    assert_eq!(Rgba::rgb(0xEA, 0xEA, 0xEA).to_string(), "#EAEAEA");
+    assert_eq!(Rgba::new(0x0C, 0x0C, 0x0C, 0x80).to_string(), "#0C0C0C80");
+    assert_eq!(Rgba::new(0x0C, 0x0C, 0x0C, 0x80).rgb_hex(), "#0C0C0C");
+}
 
 #[test]
 fn test_color_new() {
+    let black = Rgba::rgb(0x00, 0x00, 0x00);
+    let white = Rgba::rgb(0xFF, 0xFF, 0xFF);
+    let grey = Rgba::rgb(0xEA, 0xEA, 0xEA);
+    let purple = Rgba::rgb(0xFF, 0x00, 0xFF);
+
     assert_eq!(
         Color::new(),
         Color {
@@ -64,40 +163,48 @@ fn test_color_new() {
         }
     );
     assert_eq!(
-        Color::new().text_fg("#000000").icon_fg("#000000"),
+        Color::new().text_fg(black).icon_fg(black),
         Color {
-            fg: Some("^c#000000^".to_string()),
+            fg: Some(black),
             bg: None,
-            icon_fg: Some("^c#000000^".to_string()),
+            icon_fg: Some(black),
             icon_bg: None,
         }
     );
     assert_eq!(
-        Color::new().text_bg("#FFFFFF").icon_bg("#FFFFFF"),
+        Color::new().text_bg(white).icon_bg(white),
         Color {
             fg: None,
-            bg: Some("^b#FFFFFF^".to_string()),
+            bg: Some(white),
             icon_fg: None,
-            icon_bg: Some("^b#FFFFFF^".to_string())
+            icon_bg: Some(white)
         }
     );
     assert_eq!(
-        Color::new().text("#000000", "#FFFFFF"),
+        Color::new().text(black, white),
         Color {
-            fg: Some("^c#000000^".to_string()),
-            bg: Some("^b#FFFFFF^".to_string()),
+            fg: Some(black),
+            bg: Some(white),
             icon_fg: None,
             icon_bg: None,
         }
     );
     assert_eq!(
-        Color::new().icon("#EAEAEA", "#FF00FF"),
+        Color::new().icon(grey, purple),
         Color {
-            fg: Some("^c#000000^".to_string()),
-            bg: Some("^b#FFFFFF^".to_string()),
-            icon_fg: Some("^c#EAEAEA^".to_string()),
-            icon_bg: Some("^b#FF00FF^".to_string()),
+            fg: None,
+            bg: None,
+            icon_fg: Some(grey),
+            icon_bg: Some(purple),
+        }
+    );
+    assert_eq!(
+        Color::new().text(black, white).icon(grey, purple),
+        Color {
+            fg: Some(black),
+            bg: Some(white),
+            icon_fg: Some(grey),
+            icon_bg: Some(purple),
         }
     );
 }
-
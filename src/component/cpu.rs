@@ -1,65 +1,332 @@
 use super::widget::Block;
+use super::Component;
+use super::SysRoot;
+use crate::config::{CpuConfig, CpuFreqConfig, FreqMode};
+use async_trait::async_trait;
+use std::collections::VecDeque;
 use std::time::Duration;
-use tokio::fs;
-use tokio::time::sleep;
 
+/// Cumulative CPU time in `USER_HZ` ticks since boot.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
 struct CpuStat {
-    sum: i32,
-    active: i32,
+    sum: u64,
+    active: u64,
 }
 
-async fn get_stats() -> Option<CpuStat> {
-    let status = fs::read_to_string("/proc/stat").await.ok()?;
-    let mut time = Vec::new();
-    for line in status.lines() {
-        if line.starts_with("cpu") {
-            // remove the "cpu" prefix
-            time = line
-                .split_whitespace()
-                .skip(1)
-                .map(|x| {
-                    x.parse::<i32>()
-                        .expect("Fail to parse the /proc/stat file, please check your system")
-                })
-                .collect();
-            break;
+impl CpuStat {
+    /// Ratio of active time since `before`, from 0 to 1. The counters of a core can
+    /// restart after hotplugging it, which counts as 0.
+    fn usage(&self, before: &CpuStat) -> f32 {
+        let sum = self.sum.saturating_sub(before.sum);
+        if sum == 0 {
+            return 0.0;
         }
+        let active = self.active.saturating_sub(before.active).min(sum);
+        active as f32 / sum as f32
+    }
+}
+
+/// Time of all the CPUs together, and of each core in order.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct CpuStats {
+    total: CpuStat,
+    cores: Vec<CpuStat>,
+}
+
+impl CpuStats {
+    /// Usage of every core since `before`. Cores missing in `before` are measured since
+    /// boot.
+    fn core_usages(&self, before: &CpuStats) -> Vec<f32> {
+        self.cores
+            .iter()
+            .enumerate()
+            .map(|(i, core)| core.usage(before.cores.get(i).unwrap_or(&CpuStat::default())))
+            .collect()
     }
+}
+
+/// Parse a `cpu` line of /proc/stat, None if it has unexpected content.
+fn parse_stat(line: &str) -> Option<CpuStat> {
+    // remove the "cpu" prefix
+    let time = line
+        .split_whitespace()
+        .skip(1)
+        .map(|x| x.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
 
     if time.len() < 8 {
         return None;
     }
 
-    let sum: i32 = time.iter().sum();
+    let sum: u64 = time.iter().sum();
     // idle + iowait
     let inactive = time[3] + time[4];
 
     Some(CpuStat {
         sum,
-        active: sum - inactive,
+        active: sum.saturating_sub(inactive),
     })
 }
 
-pub async fn avg_load() -> Option<Block> {
-    let before = get_stats().await?;
+async fn get_stats(root: &SysRoot) -> Option<CpuStats> {
+    let status = root.read_to_string("/proc/stat").await.ok()?;
+    let mut total = None;
+    let mut cores = Vec::new();
+    for line in status.lines() {
+        if line.starts_with("cpu ") {
+            total = parse_stat(line);
+        } else if line.starts_with("cpu") {
+            cores.extend(parse_stat(line));
+        }
+    }
 
-    sleep(Duration::from_secs(1)).await;
+    Some(CpuStats {
+        total: total?,
+        cores,
+    })
+}
 
-    let after = get_stats().await?;
+/// Build a component to show the average CPU usage, and optionally the usage of each
+/// core or the recent average usage as a graph. The usage is measured between two
+/// updates from /proc/stat, the first sample is taken when the component is built.
+pub struct AvgLoad {
+    root: SysRoot,
+    config: CpuConfig,
+    /// Sample of the last update, None if /proc/stat was not readable
+    last: Option<CpuStats>,
+    /// Recent average usage, the oldest first
+    history: VecDeque<f32>,
+}
 
-    // get total
-    let sum = (before.sum - after.sum) as f32;
-    // use active time / total
-    let avg = ((before.active - after.active) as f32) / sum;
+impl AvgLoad {
+    pub async fn new(config: &CpuConfig, root: &SysRoot) -> Self {
+        Self {
+            root: root.clone(),
+            config: config.clone(),
+            last: get_stats(root).await,
+            history: VecDeque::with_capacity(config.history),
+        }
+    }
+}
+
+#[async_trait]
+impl Component for AvgLoad {
+    fn name(&self) -> &'static str {
+        "cpu"
+    }
 
-    Some(
-        Block::new("﬙", format!("{:.2} %", avg * 100.0))
-            .text_fg("#EAEAEA")
-            .icon_fg("#EAEAEA"),
-    )
+    fn interval(&self) -> Duration {
+        self.config.interval.or(2)
+    }
+
+    async fn update(&mut self) -> Option<Block> {
+        let stats = get_stats(&self.root).await?;
+        // hidden until there are two samples, the counters alone are the average since
+        // boot
+        let before = self.last.replace(stats)?;
+        let now = self.last.as_ref()?;
+
+        let avg = now.total.usage(&before.total);
+        let block = load_block(avg, &self.config);
+        if self.config.per_core {
+            return Some(block.graph(now.core_usages(&before)));
+        }
+        if self.config.history == 0 {
+            return Some(block);
+        }
+
+        if self.history.len() == self.config.history {
+            self.history.pop_front();
+        }
+        self.history.push_back(avg);
+        Some(block.graph(self.history.iter().copied().collect()))
+    }
+}
+
+/// `avg` is the ratio of active time, from 0 to 1.
+pub(super) fn load_block(avg: f32, config: &CpuConfig) -> Block {
+    let perc = avg * 100.0;
+    Block::new("﬙", format!("{:.2} %", perc))
+        .color(&config.color)
+        .threshold(perc as f64, &config.thresholds)
+}
+
+/// Build a component to show the CPU frequency and the scaling governor, read from
+/// cpufreq in sysfs. Hidden if cpufreq is unavailable.
+pub struct CpuFreq {
+    root: SysRoot,
+    config: CpuFreqConfig,
+}
+
+impl CpuFreq {
+    pub fn new(config: &CpuFreqConfig, root: &SysRoot) -> Self {
+        Self {
+            root: root.clone(),
+            config: config.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl Component for CpuFreq {
+    fn name(&self) -> &'static str {
+        "cpu_freq"
+    }
+
+    fn interval(&self) -> Duration {
+        self.config.interval.or(5)
+    }
+
+    async fn update(&mut self) -> Option<Block> {
+        let (freqs, governors) = get_freqs(&self.root).await;
+        let khz = match self.config.mode {
+            FreqMode::Average if !freqs.is_empty() => {
+                freqs.iter().sum::<u64>() / freqs.len() as u64
+            }
+            FreqMode::Average => return None,
+            FreqMode::Max => *freqs.iter().max()?,
+        };
+        Some(freq_block(
+            khz as f64 / 1_000_000.0,
+            &governors.join("/"),
+            &self.config,
+        ))
+    }
+}
+
+/// Current frequency in kHz of every online core with cpufreq, and the distinct
+/// governors in order.
+async fn get_freqs(root: &SysRoot) -> (Vec<u64>, Vec<String>) {
+    const CPU: &str = "/sys/devices/system/cpu";
+
+    let mut freqs = Vec::new();
+    let mut governors = Vec::new();
+    for name in root.list(CPU).await {
+        // skip the entries like cpufreq and cpuidle
+        let is_core = matches!(name.strip_prefix("cpu").map(str::parse::<u32>), Some(Ok(_)));
+        if !is_core {
+            continue;
+        }
+
+        let cpufreq = format!("{}/{}/cpufreq", CPU, name);
+        if let Some(freq) = root.read(format!("{}/scaling_cur_freq", cpufreq)).await {
+            freqs.push(freq);
+        }
+        if let Some(governor) = root
+            .read::<String, _>(format!("{}/scaling_governor", cpufreq))
+            .await
+        {
+            if !governors.contains(&governor) {
+                governors.push(governor);
+            }
+        }
+    }
+    (freqs, governors)
+}
+
+pub(super) fn freq_block(ghz: f64, governor: &str, config: &CpuFreqConfig) -> Block {
+    let text = if governor.is_empty() {
+        format!("{:.2} GHz", ghz)
+    } else {
+        format!("{:.2} GHz {}", ghz, governor)
+    };
+    Block::new("", text)
+        .color(&config.color)
+        .threshold(ghz, &config.thresholds)
+}
+
+#[tokio::test]
+async fn test_get_stats() {
+    let stats = get_stats(&SysRoot::fixture("cpu")).await.unwrap();
+    assert_eq!(stats.total.sum, 60377929);
+    assert_eq!(stats.total.active, 60377929 - 46828483 - 16683);
+    assert_eq!(stats.cores.len(), 4);
+    assert_eq!(stats.cores[2].sum, 14922991);
+    assert_eq!(stats.cores[2].active, 14922991 - 9921740 - 3610);
+
+    assert!(get_stats(&SysRoot::fixture("battery/full")).await.is_none());
+
+    // beyond i32 after a few weeks of uptime
+    let stat = parse_stat("cpu  3000000000 0 0 5000000000 0 0 0 0 0 0").unwrap();
+    assert_eq!((stat.sum, stat.active), (8000000000, 3000000000));
+    assert!(parse_stat("cpu  1 2 3 4 5 6 7 x 0 0").is_none());
+}
+
+#[tokio::test]
+async fn test_sample_between_updates() {
+    let config = CpuConfig {
+        per_core: true,
+        ..CpuConfig::default()
+    };
+    let mut load = AvgLoad::new(&config, &SysRoot::fixture("cpu")).await;
+
+    // measured since the component is built, not since boot
+    let block = load.update().await.unwrap();
+    assert_eq!(block.to_string(), "﬙ 0.00 % ▁▁▁▁");
+
+    load.root = SysRoot::fixture("cpu-next");
+    let block = load.update().await.unwrap();
+    assert_eq!(block.to_string(), "﬙ 50.00 % █▁▅▅");
+    assert_eq!(block.graph_values(), [1.0, 0.0, 0.5, 0.5]);
+
+    // no change since the last update
+    let block = load.update().await.unwrap();
+    assert_eq!(block.graph_values(), [0.0; 4]);
+
+    let before = CpuStat {
+        sum: 100,
+        active: 20,
+    };
+    let after = CpuStat {
+        sum: 300,
+        active: 70,
+    };
+    assert_eq!(after.usage(&before), 0.25);
+    assert_eq!(before.usage(&after), 0.0);
+
+    // no sample when built, hidden in the first update
+    let mut load = AvgLoad::new(&config, &SysRoot::fixture("battery/full")).await;
+    load.root = SysRoot::fixture("cpu");
+    assert!(load.update().await.is_none());
+    load.root = SysRoot::fixture("cpu-next");
+    assert_eq!(load.update().await.unwrap().text(), "50.00 %");
 }
 
 #[tokio::test]
-async fn test() {
-    dbg!(avg_load().await);
+async fn test_history() {
+    let config = CpuConfig {
+        history: 2,
+        ..CpuConfig::default()
+    };
+    let mut load = AvgLoad::new(&config, &SysRoot::fixture("cpu")).await;
+    let block = load.update().await.unwrap();
+    assert_eq!(block.graph_values(), [0.0]);
+
+    load.root = SysRoot::fixture("cpu-next");
+    let block = load.update().await.unwrap();
+    assert_eq!(block.graph_values().len(), 2);
+    assert_eq!(block.graph_values()[1], 0.5);
+
+    let block = load.update().await.unwrap();
+    assert_eq!(block.graph_values(), [0.5, 0.0]);
+    assert_eq!(block.to_string(), "﬙ 0.00 % ▅▁");
+}
+
+#[tokio::test]
+async fn test_cpu_freq() {
+    let root = SysRoot::fixture("cpu");
+    let (freqs, governors) = get_freqs(&root).await;
+    assert_eq!(freqs, [1200000, 3400000, 800000, 2600000]);
+    assert_eq!(governors, ["powersave"]);
+
+    let mut config = CpuFreqConfig::default();
+    let block = CpuFreq::new(&config, &root).update().await.unwrap();
+    assert_eq!(block.to_string(), " 2.00 GHz powersave");
+
+    config.mode = FreqMode::Max;
+    let block = CpuFreq::new(&config, &root).update().await.unwrap();
+    assert_eq!(block.to_string(), " 3.40 GHz powersave");
+
+    let root = SysRoot::fixture("battery/full");
+    assert!(CpuFreq::new(&config, &root).update().await.is_none());
 }
@@ -1,13 +1,55 @@
-/// Create a date component for bar
 use super::widget::Block;
-use chrono::prelude::Local;
-
-pub fn date_and_time() -> Option<Block> {
-    let now = Local::now();
-    Some(
-        Block::new("", now.format("%B/%d %I:%M %p").to_string())
-            .text_fg("#EAEAEA")
-            .icon_fg("#EAEAEA"),
-    )
+use super::Component;
+use crate::config::DateConfig;
+use async_trait::async_trait;
+use chrono::prelude::{Local, TimeZone};
+use std::fmt;
+use std::time::Duration;
+use tokio::time::sleep;
+
+/// Create a date component for bar
+pub struct DateTime {
+    config: DateConfig,
+}
+
+impl DateTime {
+    pub fn new(config: &DateConfig) -> Self {
+        Self {
+            config: config.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl Component for DateTime {
+    fn name(&self) -> &'static str {
+        "date"
+    }
+
+    fn interval(&self) -> Duration {
+        self.config.interval.or(60)
+    }
+
+    async fn update(&mut self) -> Option<Block> {
+        date_and_time(&self.config)
+    }
+
+    async fn wait(&mut self) {
+        // Align the tick to the multiple of interval, so the clock doesn't lag behind
+        let interval = self.interval().as_millis() as i64;
+        let elapsed = Local::now().timestamp_millis().rem_euclid(interval);
+        sleep(Duration::from_millis((interval - elapsed) as u64)).await;
+    }
+}
+
+fn date_and_time(config: &DateConfig) -> Option<Block> {
+    Some(date_block(&Local::now(), config))
+}
+
+pub(super) fn date_block<Tz: TimeZone>(now: &chrono::DateTime<Tz>, config: &DateConfig) -> Block
+where
+    Tz::Offset: fmt::Display,
+{
+    Block::new("", now.format(&config.format).to_string()).color(&config.color)
 }
 
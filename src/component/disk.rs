@@ -0,0 +1,144 @@
+use super::widget::{format_gib, Block};
+use super::Component;
+use super::SysRoot;
+use crate::config::{DiskConfig, DiskShow};
+use async_trait::async_trait;
+use nix::sys::statvfs::statvfs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Space of a filesystem in bytes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct DiskUsage {
+    pub used: u64,
+    /// Free space for unprivileged users, excluding the reserved blocks
+    pub available: u64,
+}
+
+impl DiskUsage {
+    /// Percentage of the used space, counted the same way as `df`.
+    fn used_percent(&self) -> f64 {
+        let total = self.used + self.available;
+        if total == 0 {
+            0.0
+        } else {
+            self.used as f64 * 100.0 / total as f64
+        }
+    }
+}
+
+/// Build a component to show the space of a mount point. It is hidden while the mount
+/// point is not mounted, like an unplugged USB drive.
+pub struct Disk {
+    root: SysRoot,
+    config: DiskConfig,
+    mount: String,
+}
+
+impl Disk {
+    pub fn new(config: &DiskConfig, mount: &str, root: &SysRoot) -> Self {
+        Self {
+            root: root.clone(),
+            config: config.clone(),
+            mount: mount.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Component for Disk {
+    fn name(&self) -> &'static str {
+        "disk"
+    }
+
+    fn interval(&self) -> Duration {
+        self.config.interval.or(30)
+    }
+
+    async fn update(&mut self) -> Option<Block> {
+        // statvfs on a directory that is no longer mounted reports the parent filesystem
+        let mounts = self.root.read_to_string("/proc/mounts").await.ok()?;
+        if !mount_points(&mounts).any(|mount| mount == self.mount) {
+            return None;
+        }
+
+        let usage = disk_usage(self.root.path(&self.mount)).await?;
+        Some(disk_block(&self.mount, &usage, &self.config))
+    }
+}
+
+/// Mount points in /proc/mounts, with the escaped whitespace restored.
+fn mount_points(mounts: &str) -> impl Iterator<Item = String> + '_ {
+    mounts
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .map(|mount| {
+            mount
+                .replace("\\040", " ")
+                .replace("\\011", "\t")
+                .replace("\\012", "\n")
+                .replace("\\134", "\\")
+        })
+}
+
+/// Run statvfs on the blocking thread pool, it can hang on an unreachable network
+/// filesystem.
+async fn disk_usage(path: PathBuf) -> Option<DiskUsage> {
+    let stat = tokio::task::spawn_blocking(move || statvfs(&path))
+        .await
+        .ok()?
+        .ok()?;
+    let size = stat.fragment_size() as u64;
+    Some(DiskUsage {
+        used: (stat.blocks() - stat.blocks_free()) as u64 * size,
+        available: stat.blocks_available() as u64 * size,
+    })
+}
+
+pub(super) fn disk_block(mount: &str, usage: &DiskUsage, config: &DiskConfig) -> Block {
+    let percent = usage.used_percent();
+    let text = match config.show {
+        DiskShow::Free => format!(
+            "{} {} {:.0} %",
+            mount,
+            format_gib(usage.available),
+            100.0 - percent
+        ),
+        DiskShow::Used => format!("{} {} {:.0} %", mount, format_gib(usage.used), percent),
+    };
+    Block::new("", text)
+        .color(&config.color)
+        .threshold(percent, &config.thresholds)
+}
+
+#[test]
+fn test_mount_points() {
+    let mounts = std::fs::read_to_string(SysRoot::fixture("disk").path("/proc/mounts")).unwrap();
+    assert_eq!(
+        mount_points(&mounts).collect::<Vec<_>>(),
+        ["/", "/proc", "/sys", "/boot", "/mnt/my data"]
+    );
+}
+
+#[tokio::test]
+async fn test_disk() {
+    let root = SysRoot::fixture("disk");
+    let config = DiskConfig::default();
+    let block = Disk::new(&config, "/", &root).update().await.unwrap();
+    assert!(block.text().starts_with("/ "));
+
+    // the directory exists but nothing is mounted on it
+    assert!(Disk::new(&config, "/mnt/usb", &root)
+        .update()
+        .await
+        .is_none());
+
+    let usage = DiskUsage {
+        used: 3 << 30,
+        available: 1 << 30,
+    };
+    assert_eq!(
+        disk_block("/home", &usage, &config).to_string(),
+        " /home 1.0 GiB 25 %"
+    );
+}
@@ -1,8 +1,11 @@
+use super::bus::system_bus;
 use super::widget::Block;
+use super::Component;
+use crate::config::BlockConfig;
 use anyhow::{Context, Result};
+use async_trait::async_trait;
 use dbus::nonblock::{stdintf::org_freedesktop_dbus::Properties, Proxy, SyncConnection};
 use dbus::Path;
-use dbus_tokio::connection;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -14,17 +17,16 @@ use std::time::Duration;
 pub struct HeadsetBattery<'a> {
     proxy: Option<Proxy<'a, Arc<SyncConnection>>>,
     conn: Arc<SyncConnection>,
+    config: BlockConfig,
 }
 
 impl<'a> HeadsetBattery<'a> {
-    pub async fn new() -> Result<HeadsetBattery<'a>> {
-        let (resource, conn) = connection::new_system_sync()?;
-        // hold the connection in other thread
-        tokio::spawn(async {
-            resource.await;
-        });
-
-        let mut bat = Self { proxy: None, conn };
+    pub async fn new(config: &BlockConfig) -> Result<HeadsetBattery<'a>> {
+        let mut bat = Self {
+            proxy: None,
+            conn: system_bus()?,
+            config: config.clone(),
+        };
 
         // try get device at initialize
         bat.enum_devices().await?;
@@ -32,7 +34,7 @@ impl<'a> HeadsetBattery<'a> {
         Ok(bat)
     }
 
-    pub async fn update(&mut self) -> Option<Block> {
+    async fn battery(&mut self) -> Option<Block> {
         // check and update headset device
         self.enum_devices().await.ok()?;
 
@@ -48,11 +50,7 @@ impl<'a> HeadsetBattery<'a> {
             })
             .ok()?;
 
-        Some(
-            Block::new("", format!("{:.0}%", percentage))
-                .text_fg("#EAEAEA")
-                .icon_fg("#EAEAEA"),
-        )
+        Some(headset_block(percentage, &self.config))
     }
 
     async fn enum_devices(&mut self) -> Result<()> {
@@ -88,3 +86,24 @@ impl<'a> HeadsetBattery<'a> {
         Ok(())
     }
 }
+
+#[async_trait]
+impl Component for HeadsetBattery<'static> {
+    fn name(&self) -> &'static str {
+        "headset_battery"
+    }
+
+    fn interval(&self) -> Duration {
+        self.config.interval.or(10)
+    }
+
+    async fn update(&mut self) -> Option<Block> {
+        self.battery().await
+    }
+}
+
+pub(super) fn headset_block(percentage: f64, config: &BlockConfig) -> Block {
+    Block::new("", format!("{:.0}%", percentage))
+        .color(&config.color)
+        .threshold(percentage, &config.thresholds)
+}
@@ -0,0 +1,351 @@
+use super::widget::Block;
+use super::Component;
+use crate::config::IpConfig;
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::channel::mpsc::UnboundedReceiver;
+use futures::stream::{StreamExt, TryStreamExt};
+use rtnetlink::constants::{RTMGRP_IPV4_IFADDR, RTMGRP_IPV6_IFADDR, RTMGRP_LINK};
+use rtnetlink::packet::nlas::{address, link};
+use rtnetlink::packet::{
+    AddressMessage, LinkMessage, NetlinkMessage, NetlinkPayload, RtnlMessage, AF_INET, AF_INET6,
+    IFF_LOWER_UP, IFF_UP, RT_SCOPE_LINK,
+};
+use rtnetlink::sys::{AsyncSocket, SocketAddr};
+use rtnetlink::Handle;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
+use tracing::error;
+
+/// Link state and addresses of a network interface.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Interface {
+    pub name: String,
+    /// Up with carrier, a cable is plugged or the tunnel is established
+    pub up: bool,
+    pub addresses: Vec<IpAddr>,
+}
+
+/// Build a component to show the addresses of the network interfaces from rtnetlink.
+/// It is updated on the link and address notifications, and reconnects on the
+/// interval when the netlink socket is lost.
+pub struct IpAddress {
+    config: IpConfig,
+    handle: Option<Handle>,
+    /// Link and address notifications, None if not connected yet
+    events: Option<UnboundedReceiver<(NetlinkMessage<RtnlMessage>, SocketAddr)>>,
+}
+
+impl IpAddress {
+    pub fn new(config: &IpConfig) -> Self {
+        Self {
+            config: config.clone(),
+            handle: None,
+            events: None,
+        }
+    }
+
+    /// Open a netlink socket subscribed to the link and address changes.
+    fn connect(&mut self) -> Result<Handle> {
+        let (mut connection, handle, events) = rtnetlink::new_connection()?;
+        let groups = RTMGRP_LINK | RTMGRP_IPV4_IFADDR | RTMGRP_IPV6_IFADDR;
+        connection
+            .socket_mut()
+            .socket_mut()
+            .bind(&SocketAddr::new(0, groups))?;
+        tokio::spawn(connection);
+
+        self.handle = Some(handle.clone());
+        self.events = Some(events);
+        Ok(handle)
+    }
+
+    async fn interfaces(&mut self) -> Result<Vec<Interface>> {
+        let handle = match &self.handle {
+            Some(handle) => handle.clone(),
+            None => self.connect()?,
+        };
+
+        let result = async {
+            let links: Vec<LinkMessage> = handle.link().get().execute().try_collect().await?;
+            let addresses: Vec<AddressMessage> =
+                handle.address().get().execute().try_collect().await?;
+            Ok::<_, rtnetlink::Error>((links, addresses))
+        }
+        .await;
+
+        match result {
+            Ok((links, addresses)) => Ok(interfaces(&links, &addresses, &self.config)),
+            Err(e) => {
+                // connect again in the next update
+                self.handle = None;
+                self.events = None;
+                Err(e.into())
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Component for IpAddress {
+    fn name(&self) -> &'static str {
+        "ip"
+    }
+
+    fn interval(&self) -> Duration {
+        self.config.interval.or(60)
+    }
+
+    async fn update(&mut self) -> Option<Block> {
+        let interfaces = self
+            .interfaces()
+            .await
+            .map_err(|e| error!("Fail to get the network interfaces: {}", e))
+            .ok()?;
+        ip_block(&interfaces, &self.config)
+    }
+
+    async fn wait(&mut self) {
+        while let Some(events) = self.events.as_mut() {
+            match events.next().await {
+                Some((message, _)) => {
+                    if is_change(&message.payload) {
+                        return;
+                    }
+                }
+                // the channel is closed when the socket is lost
+                None => {
+                    self.handle = None;
+                    self.events = None;
+                }
+            }
+        }
+        tokio::time::sleep(self.interval()).await;
+    }
+}
+
+/// Return true if the notification is about a link or an address, the routes and the
+/// neighbours are ignored.
+fn is_change(payload: &NetlinkPayload<RtnlMessage>) -> bool {
+    matches!(
+        payload,
+        NetlinkPayload::InnerMessage(
+            RtnlMessage::NewLink(_)
+                | RtnlMessage::DelLink(_)
+                | RtnlMessage::NewAddress(_)
+                | RtnlMessage::DelAddress(_)
+        )
+    )
+}
+
+/// Match the addresses to the links, keeping the selected interfaces in the configured
+/// order, or all but loopback in the kernel order if none is selected.
+fn interfaces(
+    links: &[LinkMessage],
+    addresses: &[AddressMessage],
+    config: &IpConfig,
+) -> Vec<Interface> {
+    let mut interfaces: Vec<(u32, Interface)> = links
+        .iter()
+        .filter_map(|msg| {
+            let name = msg.nlas.iter().find_map(|nla| match nla {
+                link::Nla::IfName(name) => Some(name.clone()),
+                _ => None,
+            })?;
+            let up = msg.header.flags & (IFF_UP | IFF_LOWER_UP) == IFF_UP | IFF_LOWER_UP;
+            let interface = Interface {
+                name,
+                up,
+                addresses: Vec::new(),
+            };
+            Some((msg.header.index, interface))
+        })
+        .filter(|(_, interface)| match config.interfaces.is_empty() {
+            true => interface.name != "lo",
+            false => config.interfaces.contains(&interface.name),
+        })
+        .collect();
+
+    for msg in addresses {
+        // skip the link-local addresses, which every IPv6 interface has
+        if msg.header.scope == RT_SCOPE_LINK {
+            continue;
+        }
+        let family = msg.header.family as u16;
+        if family == AF_INET6 && !config.ipv6 {
+            continue;
+        }
+        let address = msg.nlas.iter().find_map(|nla| match nla {
+            // Local is the address of the interface on point-to-point links, where
+            // Address is the peer
+            address::Nla::Local(bytes) => parse_address(family, bytes),
+            _ => None,
+        });
+        let address = address.or_else(|| {
+            msg.nlas.iter().find_map(|nla| match nla {
+                address::Nla::Address(bytes) => parse_address(family, bytes),
+                _ => None,
+            })
+        });
+
+        let interface = interfaces
+            .iter_mut()
+            .find(|(index, _)| *index == msg.header.index);
+        if let (Some((_, interface)), Some(address)) = (interface, address) {
+            interface.addresses.push(address);
+        }
+    }
+
+    let mut interfaces: Vec<Interface> = interfaces.into_iter().map(|(_, i)| i).collect();
+    if !config.interfaces.is_empty() {
+        interfaces.sort_by_key(|interface| {
+            config
+                .interfaces
+                .iter()
+                .position(|name| *name == interface.name)
+        });
+    }
+    interfaces
+}
+
+fn parse_address(family: u16, bytes: &[u8]) -> Option<IpAddr> {
+    match family {
+        AF_INET => {
+            let bytes: [u8; 4] = bytes.try_into().ok()?;
+            Some(Ipv4Addr::from(bytes).into())
+        }
+        AF_INET6 => {
+            let bytes: [u8; 16] = bytes.try_into().ok()?;
+            Some(Ipv6Addr::from(bytes).into())
+        }
+        _ => None,
+    }
+}
+
+/// Hidden if there is no interface to show.
+pub(super) fn ip_block(interfaces: &[Interface], config: &IpConfig) -> Option<Block> {
+    let text: Vec<String> = interfaces
+        .iter()
+        .filter(|interface| interface.up || config.show_down)
+        .map(|interface| {
+            if !interface.up {
+                return format!("{} down", interface.name);
+            }
+            let mut text = interface.name.clone();
+            for address in &interface.addresses {
+                text.push_str(&format!(" {}", address));
+            }
+            text
+        })
+        .collect();
+
+    if text.is_empty() {
+        return None;
+    }
+    Some(Block::new("", text.join("  ")).color(&config.color))
+}
+
+#[cfg(test)]
+fn link_message(index: u32, name: &str, flags: u32) -> LinkMessage {
+    let mut msg = LinkMessage::default();
+    msg.header.index = index;
+    msg.header.flags = flags;
+    msg.nlas.push(link::Nla::IfName(name.to_string()));
+    msg
+}
+
+#[cfg(test)]
+fn address_message(index: u32, address: IpAddr, scope: u8) -> AddressMessage {
+    let mut msg = AddressMessage::default();
+    msg.header.index = index;
+    msg.header.scope = scope;
+    let bytes = match address {
+        IpAddr::V4(address) => {
+            msg.header.family = AF_INET as u8;
+            address.octets().to_vec()
+        }
+        IpAddr::V6(address) => {
+            msg.header.family = AF_INET6 as u8;
+            address.octets().to_vec()
+        }
+    };
+    msg.nlas.push(address::Nla::Address(bytes.clone()));
+    msg.nlas.push(address::Nla::Local(bytes));
+    msg
+}
+
+#[test]
+fn test_interfaces() {
+    let running = IFF_UP | IFF_LOWER_UP;
+    let links = [
+        link_message(1, "lo", running),
+        link_message(2, "enp3s0", running),
+        link_message(3, "wg0", running),
+        // docker bridge without any container
+        link_message(4, "docker0", IFF_UP),
+    ];
+    let addresses = [
+        address_message(1, "127.0.0.1".parse().unwrap(), 254),
+        address_message(2, "192.168.1.20".parse().unwrap(), 0),
+        address_message(2, "fe80::1".parse().unwrap(), RT_SCOPE_LINK),
+        address_message(2, "2001:db8::20".parse().unwrap(), 0),
+        address_message(3, "10.8.0.2".parse().unwrap(), 0),
+        address_message(4, "172.17.0.1".parse().unwrap(), 0),
+    ];
+
+    let mut config = IpConfig::default();
+    let all = interfaces(&links, &addresses, &config);
+    assert_eq!(
+        all.iter().map(|i| i.name.as_str()).collect::<Vec<_>>(),
+        ["enp3s0", "wg0", "docker0"]
+    );
+    assert_eq!(
+        ip_block(&all, &config).unwrap().text(),
+        "enp3s0 192.168.1.20 2001:db8::20  wg0 10.8.0.2"
+    );
+
+    config.interfaces = vec!["docker0".to_string(), "enp3s0".to_string()];
+    config.ipv6 = false;
+    config.show_down = true;
+    let selected = interfaces(&links, &addresses, &config);
+    assert_eq!(
+        ip_block(&selected, &config).unwrap().text(),
+        "docker0 down  enp3s0 192.168.1.20"
+    );
+
+    config.interfaces = vec!["docker0".to_string()];
+    config.show_down = false;
+    let selected = interfaces(&links, &addresses, &config);
+    assert!(ip_block(&selected, &config).is_none());
+}
+
+#[test]
+fn test_is_change() {
+    use rtnetlink::packet::RouteMessage;
+
+    let link = RtnlMessage::NewLink(link_message(2, "enp3s0", IFF_UP));
+    assert!(is_change(&NetlinkPayload::InnerMessage(link)));
+    let address = address_message(2, "192.168.1.20".parse().unwrap(), 0);
+    assert!(is_change(&NetlinkPayload::InnerMessage(
+        RtnlMessage::DelAddress(address)
+    )));
+
+    let route = RtnlMessage::NewRoute(RouteMessage::default());
+    assert!(!is_change(&NetlinkPayload::InnerMessage(route)));
+    assert!(!is_change(&NetlinkPayload::Noop));
+}
+
+#[tokio::test]
+#[ignore = "needs a netlink socket"]
+async fn test_loopback() {
+    let config = IpConfig {
+        interfaces: vec!["lo".to_string()],
+        ..IpConfig::default()
+    };
+    let mut ip = IpAddress::new(&config);
+    let interfaces = ip.interfaces().await.unwrap();
+    assert_eq!(interfaces.len(), 1);
+    assert!(interfaces[0]
+        .addresses
+        .contains(&Ipv4Addr::LOCALHOST.into()));
+}
@@ -0,0 +1,139 @@
+use super::widget::{format_gib, Block};
+use super::Component;
+use super::SysRoot;
+use crate::config::{MemoryConfig, MEMORY_PLACEHOLDERS};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Memory and swap usage in kB.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(super) struct MemInfo {
+    pub mem_total: u64,
+    pub mem_used: u64,
+    pub swap_total: u64,
+    pub swap_used: u64,
+}
+
+impl MemInfo {
+    /// Parse /proc/meminfo. Used memory is the memory not available for starting new
+    /// applications, which excludes the reclaimable caches.
+    fn parse(content: &str) -> Option<Self> {
+        let fields: HashMap<&str, u64> = content
+            .lines()
+            .filter_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                let kb = value.split_whitespace().next()?.parse().ok()?;
+                Some((key, kb))
+            })
+            .collect();
+
+        let mem_total = *fields.get("MemTotal")?;
+        let swap_total = fields.get("SwapTotal").copied().unwrap_or(0);
+        Some(Self {
+            mem_total,
+            mem_used: mem_total.saturating_sub(*fields.get("MemAvailable")?),
+            swap_total,
+            swap_used: swap_total.saturating_sub(fields.get("SwapFree").copied().unwrap_or(0)),
+        })
+    }
+
+    fn mem_percent(&self) -> f64 {
+        percent(self.mem_used, self.mem_total)
+    }
+}
+
+fn percent(used: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        used as f64 * 100.0 / total as f64
+    }
+}
+
+fn gib(kb: u64) -> String {
+    format_gib(kb * 1024)
+}
+
+/// Build a component to show the memory and swap usage from /proc/meminfo.
+pub struct Memory {
+    root: SysRoot,
+    config: MemoryConfig,
+}
+
+impl Memory {
+    pub fn new(config: &MemoryConfig, root: &SysRoot) -> Self {
+        Self {
+            root: root.clone(),
+            config: config.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl Component for Memory {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn interval(&self) -> Duration {
+        self.config.interval.or(5)
+    }
+
+    async fn update(&mut self) -> Option<Block> {
+        let content = self.root.read_to_string("/proc/meminfo").await.ok()?;
+        Some(memory_block(&MemInfo::parse(&content)?, &self.config))
+    }
+}
+
+pub(super) fn memory_block(info: &MemInfo, config: &MemoryConfig) -> Block {
+    let values = [
+        format!("{:.0}", info.mem_percent()),
+        gib(info.mem_used),
+        gib(info.mem_total),
+        format!("{:.0}", percent(info.swap_used, info.swap_total)),
+        gib(info.swap_used),
+        gib(info.swap_total),
+    ];
+    let mut text = config.format.clone();
+    for (name, value) in MEMORY_PLACEHOLDERS.iter().zip(values) {
+        text = text.replace(&format!("{{{}}}", name), &value);
+    }
+
+    Block::new("", text)
+        .color(&config.color)
+        .threshold(info.mem_percent(), &config.thresholds)
+}
+
+#[tokio::test]
+async fn test_memory() {
+    let root = SysRoot::fixture("memory");
+    let content = root.read_to_string("/proc/meminfo").await.unwrap();
+    let info = MemInfo::parse(&content).unwrap();
+    assert_eq!(
+        info,
+        MemInfo {
+            mem_total: 16303932,
+            mem_used: 16303932 - 8151966,
+            swap_total: 8388604,
+            swap_used: 8388604 - 6291453,
+        }
+    );
+
+    let mut config = MemoryConfig::default();
+    let block = Memory::new(&config, &root).update().await.unwrap();
+    assert_eq!(block.to_string(), " 50 %");
+
+    config.format = "{mem_used}/{mem_total} swap {swap_percent} %".to_string();
+    assert_eq!(
+        memory_block(&info, &config).to_string(),
+        " 7.8 GiB/15.5 GiB swap 25 %"
+    );
+
+    // no MemAvailable
+    assert!(MemInfo::parse("MemTotal: 1024 kB\nMemFree: 512 kB\n").is_none());
+    assert!(Memory::new(&config, &SysRoot::fixture("cpu"))
+        .update()
+        .await
+        .is_none());
+}
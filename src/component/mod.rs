@@ -1,4 +1,7 @@
+mod bus;
 mod color;
+mod notification;
+mod sysroot;
 mod widget;
 
 mod song;
@@ -6,15 +9,81 @@ mod datetime;
 mod volume;
 mod battery;
 mod cpu;
+mod memory;
+mod disk;
+mod network;
+mod wifi;
+mod ip;
+mod temperature;
 #[cfg(feature = "bluetooth-battery")]
 mod headset_battery;
+#[cfg(test)]
+mod snapshot;
 
 // re-export
 pub use song::SongInfo;
-pub use datetime::date_and_time;
-pub use volume::sound_volume;
+pub use datetime::DateTime;
+pub use volume::Volume;
 #[cfg(feature = "bluetooth-battery")]
 pub use headset_battery::HeadsetBattery;
-pub use battery::battery;
-pub use cpu::avg_load;
+pub use battery::Battery;
+pub use bus::{system_bus, Session};
+pub use notification::Notifier;
+pub use cpu::{AvgLoad, CpuFreq};
+pub use memory::Memory;
+pub use disk::Disk;
+pub use network::Network;
+pub use wifi::Wifi;
+pub use ip::IpAddress;
+pub use temperature::Temperature;
+pub use color::Rgba;
+pub use sysroot::SysRoot;
 pub use widget::Block;
+
+use async_trait::async_trait;
+use std::time::Duration;
+
+/// A single block on the bar. Implement this trait and register the component into the
+/// [`Registry`] to make it show up.
+#[async_trait]
+pub trait Component: Send {
+    /// Name of the component, used for logging.
+    fn name(&self) -> &'static str;
+
+    /// Preferred refresh interval of this component.
+    fn interval(&self) -> Duration {
+        Duration::from_secs(10)
+    }
+
+    /// Fetch the latest information and build the block. Return None to hide the block.
+    async fn update(&mut self) -> Option<Block>;
+
+    /// Wait until the next update is due. Sleep for the refresh interval by default,
+    /// override it to update on other schedules or events.
+    async fn wait(&mut self) {
+        tokio::time::sleep(self.interval()).await;
+    }
+}
+
+/// Ordered list of components. The bar is rendered in the same order as the
+/// components get registered.
+#[derive(Default)]
+pub struct Registry {
+    components: Vec<Box<dyn Component>>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register<C: Component + 'static>(&mut self, component: C) -> &mut Self {
+        tracing::info!("Registering component {}", component.name());
+        self.components.push(Box::new(component));
+        self
+    }
+
+    pub fn into_components(self) -> Vec<Box<dyn Component>> {
+        self.components
+    }
+}
@@ -0,0 +1,181 @@
+use super::widget::Block;
+use super::Component;
+use super::SysRoot;
+use crate::config::NetworkConfig;
+use async_trait::async_trait;
+use std::time::{Duration, Instant};
+
+/// Byte counters of an interface.
+#[derive(Debug, Clone)]
+struct Sample {
+    interface: String,
+    rx: u64,
+    tx: u64,
+    time: Instant,
+}
+
+/// Build a component to show the download and upload rates of an interface, measured
+/// between two updates from /proc/net/dev. Hidden if the interface is missing.
+pub struct Network {
+    root: SysRoot,
+    config: NetworkConfig,
+    last: Option<Sample>,
+}
+
+impl Network {
+    pub fn new(config: &NetworkConfig, root: &SysRoot) -> Self {
+        Self {
+            root: root.clone(),
+            config: config.clone(),
+            last: None,
+        }
+    }
+
+    async fn sample(&self) -> Option<Sample> {
+        let interface = match &self.config.interface {
+            Some(interface) => interface.clone(),
+            None => default_interface(&self.root.read_to_string("/proc/net/route").await.ok()?)?,
+        };
+        let dev = self.root.read_to_string("/proc/net/dev").await.ok()?;
+        let (rx, tx) = counters(&dev, &interface)?;
+        Some(Sample {
+            interface,
+            rx,
+            tx,
+            time: Instant::now(),
+        })
+    }
+}
+
+#[async_trait]
+impl Component for Network {
+    fn name(&self) -> &'static str {
+        "network"
+    }
+
+    fn interval(&self) -> Duration {
+        self.config.interval.or(2)
+    }
+
+    async fn update(&mut self) -> Option<Block> {
+        let sample = self.sample().await;
+        let before = std::mem::replace(&mut self.last, sample);
+        let now = self.last.as_ref()?;
+
+        // the default route may have moved to another interface
+        let (down, up) = match before {
+            Some(before) if before.interface == now.interface => {
+                let secs = now.time.duration_since(before.time).as_secs_f64();
+                if secs > 0.0 {
+                    (
+                        now.rx.saturating_sub(before.rx) as f64 / secs,
+                        now.tx.saturating_sub(before.tx) as f64 / secs,
+                    )
+                } else {
+                    (0.0, 0.0)
+                }
+            }
+            _ => (0.0, 0.0),
+        };
+        Some(network_block(down, up, &self.config))
+    }
+}
+
+/// Interface of the default route with the lowest metric in /proc/net/route.
+fn default_interface(route: &str) -> Option<String> {
+    const RTF_UP: u32 = 0x1;
+
+    route
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 8 || fields[1] != "00000000" || fields[7] != "00000000" {
+                return None;
+            }
+            let flags = u32::from_str_radix(fields[3], 16).ok()?;
+            let metric: u32 = fields[6].parse().ok()?;
+            (flags & RTF_UP != 0).then(|| (metric, fields[0]))
+        })
+        .min()
+        .map(|(_, interface)| interface.to_string())
+}
+
+/// Received and transmitted bytes of an interface in /proc/net/dev.
+fn counters(dev: &str, interface: &str) -> Option<(u64, u64)> {
+    dev.lines().skip(2).find_map(|line| {
+        let (name, stats) = line.split_once(':')?;
+        if name.trim() != interface {
+            return None;
+        }
+        let stats: Vec<&str> = stats.split_whitespace().collect();
+        Some((stats.first()?.parse().ok()?, stats.get(8)?.parse().ok()?))
+    })
+}
+
+/// Rate in binary units, like `1.5 MiB/s`.
+fn format_rate(bytes_per_sec: f64) -> String {
+    const UNITS: [&str; 4] = ["KiB/s", "MiB/s", "GiB/s", "TiB/s"];
+
+    if bytes_per_sec < 1024.0 {
+        return format!("{:.0} B/s", bytes_per_sec);
+    }
+    let mut value = bytes_per_sec / 1024.0;
+    let mut unit = UNITS[0];
+    for next in &UNITS[1..] {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = next;
+    }
+    format!("{:.1} {}", value, unit)
+}
+
+/// `down` and `up` are in bytes per second.
+pub(super) fn network_block(down: f64, up: f64, config: &NetworkConfig) -> Block {
+    Block::new(
+        "",
+        format!("↓ {} ↑ {}", format_rate(down), format_rate(up)),
+    )
+    .color(&config.color)
+}
+
+#[test]
+fn test_parse_net() {
+    let root = SysRoot::fixture("network");
+    let route = std::fs::read_to_string(root.path("/proc/net/route")).unwrap();
+    assert_eq!(default_interface(&route), Some("enp3s0".to_string()));
+    assert_eq!(default_interface(route.lines().next().unwrap()), None);
+
+    let dev = std::fs::read_to_string(root.path("/proc/net/dev")).unwrap();
+    assert_eq!(counters(&dev, "enp3s0"), Some((1843227491, 93481244)));
+    assert_eq!(counters(&dev, "lo"), Some((864210, 864210)));
+    assert_eq!(counters(&dev, "eth0"), None);
+
+    assert_eq!(format_rate(512.0), "512 B/s");
+    assert_eq!(format_rate(1536.0), "1.5 KiB/s");
+    assert_eq!(format_rate(3.0 * 1024.0 * 1024.0 * 1024.0), "3.0 GiB/s");
+}
+
+#[tokio::test]
+async fn test_network_rates() {
+    let mut network = Network::new(&NetworkConfig::default(), &SysRoot::fixture("network"));
+    let block = network.update().await.unwrap();
+    assert_eq!(block.text(), "↓ 0 B/s ↑ 0 B/s");
+
+    // pretend the last sample was taken 2 seconds ago
+    network.last.as_mut().unwrap().time -= Duration::from_secs(2);
+    network.root = SysRoot::fixture("network-next");
+    let block = network.update().await.unwrap();
+    assert_eq!(block.text(), "↓ 1.5 MiB/s ↑ 30.0 KiB/s");
+
+    let config = NetworkConfig {
+        interface: Some("wlan1".to_string()),
+        ..NetworkConfig::default()
+    };
+    assert!(Network::new(&config, &network.root)
+        .update()
+        .await
+        .is_none());
+}
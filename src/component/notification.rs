@@ -0,0 +1,224 @@
+use anyhow::Result;
+use dbus::arg::Variant;
+use dbus::nonblock::{Proxy, SyncConnection};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+const NOTIFICATIONS: &str = "org.freedesktop.Notifications";
+
+/// Urgency level of the desktop notification specification, except the unused `low`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Urgency {
+    Normal = 1,
+    Critical = 2,
+}
+
+/// Arguments of the `Notify` method: app name, replaced id, icon, summary, body, actions,
+/// hints and the expiration timeout
+type NotifyArgs<'a> = (
+    &'a str,
+    u32,
+    &'a str,
+    &'a str,
+    &'a str,
+    Vec<&'a str>,
+    HashMap<&'a str, Variant<u8>>,
+    i32,
+);
+
+fn notify_args<'a>(
+    replaces: u32,
+    icon: &'a str,
+    summary: &'a str,
+    body: &'a str,
+    urgency: Urgency,
+) -> NotifyArgs<'a> {
+    let hints = HashMap::from([("urgency", Variant(urgency as u8))]);
+    // -1 lets the server decide when the notification expires
+    (
+        "dwm-bar",
+        replaces,
+        icon,
+        summary,
+        body,
+        Vec::new(),
+        hints,
+        -1,
+    )
+}
+
+/// Send desktop notifications through `org.freedesktop.Notifications`.
+#[derive(Clone)]
+pub struct Notifier {
+    conn: Arc<SyncConnection>,
+}
+
+impl Notifier {
+    pub fn new(conn: Arc<SyncConnection>) -> Self {
+        Self { conn }
+    }
+
+    /// Show a notification in place of the notification `replaces`, or a new one if it
+    /// is 0. Return the id of the shown notification.
+    pub async fn notify(
+        &self,
+        replaces: u32,
+        icon: &str,
+        summary: &str,
+        body: &str,
+        urgency: Urgency,
+    ) -> Result<u32> {
+        let proxy = Proxy::new(
+            NOTIFICATIONS,
+            "/org/freedesktop/Notifications",
+            Duration::from_secs(2),
+            self.conn.clone(),
+        );
+        let args = notify_args(replaces, icon, summary, body, urgency);
+        let (id,): (u32,) = proxy.method_call(NOTIFICATIONS, "Notify", args).await?;
+        Ok(id)
+    }
+}
+
+/// A notification received by the [`stand_in`] daemon.
+#[cfg(test)]
+#[derive(Debug, PartialEq)]
+pub struct Received {
+    pub replaces: u32,
+    pub summary: String,
+    pub body: String,
+    pub urgency: u8,
+}
+
+#[cfg(test)]
+impl Received {
+    /// Read the arguments of a `Notify` method call.
+    fn read(msg: &dbus::Message) -> Self {
+        use dbus::arg::RefArg;
+
+        let mut args = msg.iter_init();
+        let _app: String = args.read().unwrap();
+        let replaces: u32 = args.read().unwrap();
+        let _icon: String = args.read().unwrap();
+        let summary: String = args.read().unwrap();
+        let body: String = args.read().unwrap();
+        let _actions: Vec<String> = args.read().unwrap();
+        let hints: HashMap<String, Variant<Box<dyn RefArg>>> = args.read().unwrap();
+        Self {
+            replaces,
+            summary,
+            body,
+            urgency: hints["urgency"].0.as_u64().unwrap() as u8,
+        }
+    }
+}
+
+/// Own the notification daemon name on the bus, and pass the received notifications
+/// to the returned channel. Notifications get id from 1 in order.
+#[cfg(test)]
+pub async fn stand_in(
+    conn: Arc<SyncConnection>,
+) -> futures::channel::mpsc::UnboundedReceiver<Received> {
+    use dbus::channel::{MatchingReceiver, Sender};
+    use dbus::message::MatchRule;
+
+    conn.request_name(NOTIFICATIONS, false, true, true)
+        .await
+        .unwrap();
+
+    let (tx, rx) = futures::channel::mpsc::unbounded();
+    let mut id = 0u32;
+    let rule = MatchRule::new_method_call()
+        .with_interface(NOTIFICATIONS)
+        .with_member("Notify");
+    conn.start_receive(
+        rule,
+        Box::new(move |msg, conn| {
+            let received = Received::read(&msg);
+            // pass the notification before replying, so it can be checked once the
+            // method call is returned
+            let receiving = tx.unbounded_send(received).is_ok();
+            id += 1;
+            let _ = conn.send(msg.method_return().append1(id));
+            receiving
+        }),
+    );
+    rx
+}
+
+#[test]
+fn test_notify_args() {
+    let mut msg = dbus::Message::new_method_call(
+        NOTIFICATIONS,
+        "/org/freedesktop/Notifications",
+        NOTIFICATIONS,
+        "Notify",
+    )
+    .unwrap();
+    msg.append_all(notify_args(
+        3,
+        "battery-caution",
+        "Battery critically low",
+        "4 % remaining",
+        Urgency::Critical,
+    ));
+
+    assert_eq!(
+        Received::read(&msg),
+        Received {
+            replaces: 3,
+            summary: "Battery critically low".to_string(),
+            body: "4 % remaining".to_string(),
+            urgency: 2,
+        }
+    );
+    let (app, _, icon): (&str, u32, &str) = msg.read3().unwrap();
+    assert_eq!((app, icon), ("dwm-bar", "battery-caution"));
+}
+
+#[tokio::test]
+#[ignore = "needs dbus-daemon"]
+async fn test_notify() {
+    use super::bus::PrivateBus;
+    use futures::StreamExt;
+
+    let bus = PrivateBus::start().expect("dbus-daemon is not installed");
+    let mut received = stand_in(bus.connect()).await;
+    let notifier = Notifier::new(bus.connect());
+
+    let id = notifier
+        .notify(
+            0,
+            "battery-low",
+            "Battery low",
+            "15 % remaining",
+            Urgency::Normal,
+        )
+        .await
+        .unwrap();
+    assert_eq!(id, 1);
+    assert_eq!(
+        received.next().await.unwrap(),
+        Received {
+            replaces: 0,
+            summary: "Battery low".to_string(),
+            body: "15 % remaining".to_string(),
+            urgency: 1,
+        }
+    );
+
+    let id = notifier
+        .notify(
+            id,
+            "battery-caution",
+            "Battery critical",
+            "",
+            Urgency::Critical,
+        )
+        .await
+        .unwrap();
+    assert_eq!(id, 2);
+    let notification = received.next().await.unwrap();
+    assert_eq!((notification.replaces, notification.urgency), (1, 2));
+}
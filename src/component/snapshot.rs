@@ -0,0 +1,359 @@
+// Rendered strings of every component with fixed inputs. Update the expected
+// strings here when a block is changed on purpose.
+
+use super::battery::{battery_block, Status};
+use super::cpu::{freq_block, load_block};
+use super::datetime::date_block;
+use super::disk::{disk_block, DiskUsage};
+#[cfg(feature = "bluetooth-battery")]
+use super::headset_battery::headset_block;
+use super::ip::{ip_block, Interface};
+use super::memory::{memory_block, MemInfo};
+use super::network::network_block;
+use super::song::song_block;
+use super::temperature::temperature_block;
+use super::volume::volume_block;
+use super::wifi::{wifi_block, Connection};
+use super::{Block, Rgba};
+use crate::config::{
+    BatteryConfig, BlockConfig, ColorConfig, CpuConfig, CpuFreqConfig, DateConfig, DiskConfig,
+    DiskShow, IpConfig, MemoryConfig, NetworkConfig, SongConfig, TemperatureConfig, Threshold,
+    UrgentColor,
+};
+use crate::output::status2d;
+use chrono::{TimeZone, Utc};
+use std::time::Duration;
+
+/// Check both the plain text and the dwm status2d rendering of the block.
+fn assert_snapshot(block: &Block, plain: &str, dwm: &str) {
+    assert_eq!(block.to_string(), plain);
+    assert_eq!(status2d(block, &UrgentColor::default()), dwm);
+}
+
+#[test]
+fn test_battery_snapshot() {
+    let config = BatteryConfig::default();
+    assert_snapshot(
+        &battery_block(42, Status::Discharging, None, &config),
+        " 42 %",
+        "^c#EAEAEA^ ^c#EAEAEA^42 %",
+    );
+    assert_snapshot(
+        &battery_block(
+            42,
+            Status::Discharging,
+            Some(Duration::from_secs(9300)),
+            &config,
+        ),
+        " 42 % 2:35",
+        "^c#EAEAEA^ ^c#EAEAEA^42 % 2:35",
+    );
+    assert_snapshot(
+        &battery_block(57, Status::Charging, None, &config),
+        " 57 %",
+        "^c#A3BE8C^ ^c#EAEAEA^57 %",
+    );
+    assert_snapshot(
+        &battery_block(100, Status::Full, None, &config),
+        " 100 %",
+        "^c#88C0D0^ ^c#EAEAEA^100 %",
+    );
+    assert_snapshot(
+        &battery_block(80, Status::NotCharging, None, &config),
+        " 80 %",
+        "^c#EBCB8B^ ^c#EAEAEA^80 %",
+    );
+    assert_snapshot(
+        &battery_block(80, Status::Unknown, None, &config),
+        " 80 %",
+        "^c#808080^ ^c#EAEAEA^80 %",
+    );
+
+    let config = BatteryConfig {
+        thresholds: vec![Threshold {
+            below: Some(20.0),
+            color: ColorConfig {
+                text_bg: Some(Rgba::rgb(0xBF, 0x61, 0x6A)),
+                ..Default::default()
+            },
+            urgent: true,
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let block = battery_block(15, Status::Discharging, None, &config);
+    assert!(block.is_urgent());
+    assert_snapshot(&block, " 15 %", "^c#EAEAEA^ ^c#EAEAEA^^b#BF616A^15 %");
+}
+
+#[test]
+fn test_cpu_snapshot() {
+    let config = CpuConfig::default();
+    assert_snapshot(
+        &load_block(0.1234, &config),
+        "﬙ 12.34 %",
+        "^c#EAEAEA^﬙ ^c#EAEAEA^12.34 %",
+    );
+    assert_snapshot(
+        &load_block(1.0, &config),
+        "﬙ 100.00 %",
+        "^c#EAEAEA^﬙ ^c#EAEAEA^100.00 %",
+    );
+    assert_snapshot(
+        &load_block(0.25, &config).graph(vec![0.0, 1.0]),
+        "﬙ 25.00 % ▁█",
+        "^c#EAEAEA^﬙ ^c#EAEAEA^25.00 % ^r0,14,3,1^^r4,3,3,12^^f8^",
+    );
+}
+
+#[test]
+fn test_cpu_freq_snapshot() {
+    assert_snapshot(
+        &freq_block(2.4, "performance", &CpuFreqConfig::default()),
+        " 2.40 GHz performance",
+        "^c#EAEAEA^ ^c#EAEAEA^2.40 GHz performance",
+    );
+}
+
+#[test]
+fn test_memory_snapshot() {
+    let info = MemInfo {
+        mem_total: 8 * 1024 * 1024,
+        mem_used: 6 * 1024 * 1024,
+        swap_total: 0,
+        swap_used: 0,
+    };
+    let config = MemoryConfig {
+        format: "{mem_percent} % {mem_used}".to_string(),
+        thresholds: vec![Threshold {
+            above: Some(70.0),
+            color: ColorConfig {
+                text_fg: Some(Rgba::rgb(0xD0, 0x87, 0x70)),
+                ..Default::default()
+            },
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    assert_snapshot(
+        &memory_block(&info, &config),
+        " 75 % 6.0 GiB",
+        "^c#EAEAEA^ ^c#D08770^75 % 6.0 GiB",
+    );
+}
+
+#[test]
+fn test_disk_snapshot() {
+    let usage = DiskUsage {
+        used: 95 << 30,
+        available: 5 << 30,
+    };
+    let config = DiskConfig {
+        show: DiskShow::Used,
+        thresholds: vec![Threshold {
+            above: Some(90.0),
+            color: ColorConfig {
+                text_bg: Some(Rgba::rgb(0xBF, 0x61, 0x6A)),
+                ..Default::default()
+            },
+            urgent: true,
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let block = disk_block("/", &usage, &config);
+    assert!(block.is_urgent());
+    assert_snapshot(
+        &block,
+        " / 95.0 GiB 95 %",
+        "^c#EAEAEA^ ^c#EAEAEA^^b#BF616A^/ 95.0 GiB 95 %",
+    );
+}
+
+#[test]
+fn test_network_snapshot() {
+    assert_snapshot(
+        &network_block(2048.0, 100.0, &NetworkConfig::default()),
+        " ↓ 2.0 KiB/s ↑ 100 B/s",
+        "^c#EAEAEA^ ^c#EAEAEA^↓ 2.0 KiB/s ↑ 100 B/s",
+    );
+}
+
+#[test]
+fn test_wifi_snapshot() {
+    let config = BlockConfig {
+        thresholds: vec![Threshold {
+            below: Some(30.0),
+            color: ColorConfig {
+                icon_fg: Some(Rgba::rgb(0xEB, 0xCB, 0x8B)),
+                ..Default::default()
+            },
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let wifi = Connection::Wifi {
+        ssid: "home".to_string(),
+        strength: 25,
+    };
+    assert_snapshot(
+        &wifi_block(&wifi, true, &config),
+        " home 25% VPN",
+        "^c#EBCB8B^ ^c#EAEAEA^home 25% VPN",
+    );
+    assert_snapshot(
+        &wifi_block(&Connection::Disconnected, false, &config),
+        " disconnected",
+        "^c#EAEAEA^ ^c#EAEAEA^disconnected",
+    );
+}
+
+#[test]
+fn test_ip_snapshot() {
+    let interfaces = [
+        Interface {
+            name: "wg0".to_string(),
+            up: true,
+            addresses: vec!["10.8.0.2".parse().unwrap()],
+        },
+        Interface {
+            name: "enp3s0".to_string(),
+            up: false,
+            addresses: Vec::new(),
+        },
+    ];
+    let config = IpConfig {
+        show_down: true,
+        ..Default::default()
+    };
+    assert_snapshot(
+        &ip_block(&interfaces, &config).unwrap(),
+        " wg0 10.8.0.2  enp3s0 down",
+        "^c#EAEAEA^ ^c#EAEAEA^wg0 10.8.0.2  enp3s0 down",
+    );
+}
+
+#[test]
+fn test_temperature_snapshot() {
+    let config = TemperatureConfig {
+        thresholds: vec![Threshold {
+            above: Some(80.0),
+            color: ColorConfig {
+                text_bg: Some(Rgba::rgb(0xBF, 0x61, 0x6A)),
+                ..Default::default()
+            },
+            urgent: true,
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    assert_snapshot(
+        &temperature_block(52.0, &config),
+        " 52°C",
+        "^c#EAEAEA^ ^c#EAEAEA^52°C",
+    );
+    assert_snapshot(
+        &temperature_block(91.5, &config),
+        " 92°C",
+        "^c#EAEAEA^ ^c#EAEAEA^^b#BF616A^92°C",
+    );
+}
+
+#[test]
+fn test_date_snapshot() {
+    let now = Utc.ymd(2022, 5, 14).and_hms(21, 5, 0);
+    assert_snapshot(
+        &date_block(&now, &DateConfig::default()),
+        " May/14 09:05 PM",
+        "^c#EAEAEA^ ^c#EAEAEA^May/14 09:05 PM",
+    );
+
+    let config = DateConfig {
+        format: "%Y-%m-%d %H:%M".to_string(),
+        ..Default::default()
+    };
+    assert_snapshot(
+        &date_block(&now, &config),
+        " 2022-05-14 21:05",
+        "^c#EAEAEA^ ^c#EAEAEA^2022-05-14 21:05",
+    );
+}
+
+#[test]
+fn test_volume_snapshot() {
+    let config = BlockConfig::default();
+    assert_snapshot(
+        &volume_block(65, false, &config),
+        " 65%",
+        "^c#EAEAEA^ ^c#EAEAEA^65%",
+    );
+    assert_snapshot(
+        &volume_block(65, true, &config),
+        " 65%",
+        "^c#EAEAEA^ ^c#EAEAEA^65%",
+    );
+
+    let config = BlockConfig {
+        thresholds: vec![Threshold {
+            below: Some(1.0),
+            color: ColorConfig {
+                text_fg: Some(Rgba::rgb(0x80, 0x80, 0x80)),
+                icon_fg: Some(Rgba::rgb(0x80, 0x80, 0x80)),
+                ..Default::default()
+            },
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    assert_snapshot(
+        &volume_block(65, true, &config),
+        " 65%",
+        "^c#808080^ ^c#808080^65%",
+    );
+}
+
+#[test]
+fn test_song_snapshot() {
+    let config = SongConfig::default();
+    assert_snapshot(
+        &song_block("Daft Punk", "Touch", &config),
+        "   Daft Punk - Touch ",
+        "^c#EAEAEA^^b#0C0C0C^  ^c#EAEAEA^^b#171617^ Daft Punk - Touch ",
+    );
+    assert_snapshot(
+        &song_block("", "Untitled", &config),
+        "   Anonymous - Untitled ",
+        "^c#EAEAEA^^b#0C0C0C^  ^c#EAEAEA^^b#171617^ Anonymous - Untitled ",
+    );
+
+    let config = SongConfig {
+        max_length: 12,
+        ..Default::default()
+    };
+    assert_snapshot(
+        &song_block("Daft Punk", "Touch", &config),
+        "   Daft Punk -...",
+        "^c#EAEAEA^^b#0C0C0C^  ^c#EAEAEA^^b#171617^ Daft Punk -...",
+    );
+
+    // the limit counts characters, not bytes
+    let config = SongConfig {
+        max_length: 24,
+        ..Default::default()
+    };
+    assert_snapshot(
+        &song_block("Sigur Rós", "Hoppípolla", &config),
+        "   Sigur Rós - Hoppípolla ",
+        "^c#EAEAEA^^b#0C0C0C^  ^c#EAEAEA^^b#171617^ Sigur Rós - Hoppípolla ",
+    );
+}
+
+#[cfg(feature = "bluetooth-battery")]
+#[test]
+fn test_headset_battery_snapshot() {
+    assert_snapshot(
+        &headset_block(87.6, &BlockConfig::default()),
+        " 88%",
+        "^c#EAEAEA^ ^c#EAEAEA^88%",
+    );
+}
@@ -1,30 +1,79 @@
-// FIXME: We should keep the connection
-
 use super::widget::Block;
+use super::Component;
+use crate::config::SongConfig;
 use anyhow::Result;
+use async_trait::async_trait;
 use dbus::arg;
-use dbus::nonblock::{stdintf::org_freedesktop_dbus::Properties, Proxy, SyncConnection};
-use dbus_tokio::connection;
+use dbus::message::MatchRule;
+use dbus::nonblock::{stdintf::org_freedesktop_dbus::Properties, MsgMatch, Proxy, SyncConnection};
+use dbus::Message;
+use futures::channel::mpsc::UnboundedReceiver;
+use futures::stream::{self, Select, StreamExt};
 use std::sync::Arc;
 use std::time::Duration;
-use tracing::{error, info};
+use tracing::error;
+
+/// Every MPRIS player owns a bus name with this prefix
+const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
 
 pub struct SongInfo {
     conn: Arc<SyncConnection>,
+    config: SongConfig,
+    /// Bus name of the current player
+    player: Option<String>,
+    /// Signals from the players and the bus, None if not subscribed yet
+    signals: Option<Select<UnboundedReceiver<Message>, UnboundedReceiver<Message>>>,
+    // hold the matches to keep receiving signals
+    _matches: Vec<MsgMatch>,
 }
 
 impl SongInfo {
-    pub fn new() -> Result<SongInfo> {
-        let (resource, conn) = connection::new_session_sync()?;
-        let _conn_handle = tokio::spawn(async {
-            info!("Holding connection to D-Bus");
-            let err = resource.await;
-            error!("Lost connection to D-Bus: {}", err);
-        });
-        Ok(SongInfo { conn })
+    pub fn new(config: &SongConfig, conn: Arc<SyncConnection>) -> SongInfo {
+        SongInfo {
+            conn,
+            config: config.clone(),
+            player: None,
+            signals: None,
+            _matches: Vec::new(),
+        }
+    }
+
+    /// Subscribe to the `PropertiesChanged` signal of the players, and the
+    /// `NameOwnerChanged` signal of the bus to know when a player appears or quits.
+    async fn subscribe(&mut self) -> Result<()> {
+        let properties =
+            MatchRule::new_signal("org.freedesktop.DBus.Properties", "PropertiesChanged")
+                .with_path("/org/mpris/MediaPlayer2");
+        let (properties, properties_stream) = self.conn.add_match(properties).await?.msg_stream();
+
+        let owner = MatchRule::new_signal("org.freedesktop.DBus", "NameOwnerChanged")
+            .with_sender("org.freedesktop.DBus");
+        let (owner, owner_stream) = self.conn.add_match(owner).await?.msg_stream();
+
+        self.signals = Some(stream::select(properties_stream, owner_stream));
+        self._matches = vec![properties, owner];
+        Ok(())
+    }
+
+    /// Return true if the signal may change the song information.
+    fn handle_signal(&mut self, msg: &Message) -> bool {
+        match msg.member().as_deref() {
+            Some("PropertiesChanged") => {
+                matches!(msg.read1::<&str>(), Ok("org.mpris.MediaPlayer2.Player"))
+            }
+            Some("NameOwnerChanged") => match msg.read1::<&str>() {
+                Ok(name) if name.starts_with(MPRIS_PREFIX) => {
+                    // a player appears or quits, find the player again in next update
+                    self.player = None;
+                    true
+                }
+                _ => false,
+            },
+            _ => false,
+        }
     }
 
-    async fn get_metadata(&self) -> Result<arg::PropMap> {
+    async fn find_player(&self) -> Result<String> {
         let proxy = Proxy::new(
             "org.freedesktop.DBus",
             "/",
@@ -35,23 +84,34 @@ impl SongInfo {
             .method_call("org.freedesktop.DBus", "ListNames", ())
             .await?;
 
-        let addr = services
-            .iter()
-            .find(|serv| serv.contains("mpris"))
-            .ok_or_else(|| anyhow::anyhow!("No mpris device found"))?;
+        services
+            .into_iter()
+            .find(|serv| serv.starts_with(MPRIS_PREFIX))
+            .ok_or_else(|| anyhow::anyhow!("No mpris device found"))
+    }
+
+    async fn get_metadata(&mut self) -> Result<arg::PropMap> {
+        let player = match self.player.take() {
+            Some(player) => player,
+            None => self.find_player().await?,
+        };
 
         let proxy = Proxy::new(
-            addr,
+            player.as_str(),
             "/org/mpris/MediaPlayer2",
             Duration::from_millis(2000),
             self.conn.clone(),
         );
-        Ok(proxy
+        let metadata = proxy
             .get("org.mpris.MediaPlayer2.Player", "Metadata")
-            .await?)
+            .await?;
+
+        // only keep the player when it is still alive
+        self.player = Some(player);
+        Ok(metadata)
     }
 
-    pub async fn song_info(&self) -> Option<Block> {
+    pub async fn song_info(&mut self) -> Option<Block> {
         let metadata = self.get_metadata().await.ok()?;
 
         let artist: Option<&Vec<String>> = arg::prop_cast(&metadata, "xesam:artist");
@@ -59,29 +119,67 @@ impl SongInfo {
         let song: Option<&String> = arg::prop_cast(&metadata, "xesam:title");
         let song = song?;
 
-        let output = format!(
-            " {} - {} ",
-            if !artist.is_empty() {
-                artist
-            } else {
-                "Anonymous".to_string()
-            },
-            song,
-        );
+        Some(song_block(&artist, song, &self.config))
+    }
+}
 
-        let text_limit = 40;
-        // trim the text
-        let output = if output.len() > text_limit {
-            let split = output.chars().take(text_limit).collect::<String>();
-            format!("{}...", split)
+pub(super) fn song_block(artist: &str, title: &str, config: &SongConfig) -> Block {
+    let output = format!(
+        " {} - {} ",
+        if !artist.is_empty() {
+            artist
         } else {
-            output
-        };
+            "Anonymous"
+        },
+        title,
+    );
+
+    let text_limit = config.max_length;
+    // trim the text
+    let output = if output.chars().count() > text_limit {
+        let split = output.chars().take(text_limit).collect::<String>();
+        format!("{}...", split)
+    } else {
+        output
+    };
+
+    Block::new(" ", output).color(&config.color)
+}
+
+#[async_trait]
+impl Component for SongInfo {
+    fn name(&self) -> &'static str {
+        "song"
+    }
+
+    fn interval(&self) -> Duration {
+        self.config.interval.or(10)
+    }
+
+    async fn update(&mut self) -> Option<Block> {
+        if self.signals.is_none() {
+            if let Err(e) = self.subscribe().await {
+                error!(
+                    "Fail to subscribe to MPRIS signals, fallback to polling: {}",
+                    e
+                );
+            }
+        }
+        self.song_info().await
+    }
 
-        Some(
-            Block::new(" ", output)
-                .icon_color("#EAEAEA", "#0C0C0C")
-                .text_color("#EAEAEA", "#171617"),
-        )
+    async fn wait(&mut self) {
+        while let Some(signals) = self.signals.as_mut() {
+            match signals.next().await {
+                Some(msg) => {
+                    if self.handle_signal(&msg) {
+                        return;
+                    }
+                }
+                // the stream ends when the connection is lost
+                None => self.signals = None,
+            }
+        }
+        tokio::time::sleep(self.interval()).await;
     }
 }
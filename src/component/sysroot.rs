@@ -0,0 +1,88 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tokio::fs;
+
+/// Directory that `/sys` and `/proc` are looked up from. It is `/` on a running system,
+/// and a fixture directory in tests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SysRoot(PathBuf);
+
+impl Default for SysRoot {
+    fn default() -> Self {
+        Self::new("/")
+    }
+}
+
+impl SysRoot {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self(root.into())
+    }
+
+    /// Fixture tree under `tests/fixtures`.
+    #[cfg(test)]
+    pub fn fixture(name: &str) -> Self {
+        Self::new(
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/fixtures")
+                .join(name),
+        )
+    }
+
+    /// Resolve an absolute path like `/proc/stat` under the root.
+    pub fn path<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        let path = path.as_ref();
+        self.0.join(path.strip_prefix("/").unwrap_or(path))
+    }
+
+    pub async fn read_to_string<P: AsRef<Path>>(&self, path: P) -> io::Result<String> {
+        fs::read_to_string(self.path(path)).await
+    }
+
+    /// Read a single value file, like the most sysfs attributes. Return None if the file
+    /// is missing or can't be parsed.
+    pub async fn read<T: FromStr, P: AsRef<Path>>(&self, path: P) -> Option<T> {
+        self.read_to_string(path).await.ok()?.trim().parse().ok()
+    }
+
+    /// Sorted names of the entries in a directory, empty if the directory is missing.
+    pub async fn list<P: AsRef<Path>>(&self, path: P) -> Vec<String> {
+        let mut names = Vec::new();
+        if let Ok(mut dir) = fs::read_dir(self.path(path)).await {
+            while let Ok(Some(entry)) = dir.next_entry().await {
+                names.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        names.sort();
+        names
+    }
+}
+
+#[tokio::test]
+async fn test_sysroot_read() {
+    let root = SysRoot::fixture("battery/charging");
+    assert_eq!(
+        root.path("/sys/class/power_supply"),
+        root.0.join("sys/class/power_supply")
+    );
+    assert_eq!(
+        root.read::<u8, _>("/sys/class/power_supply/BAT0/capacity")
+            .await,
+        Some(57)
+    );
+    assert_eq!(
+        root.read::<String, _>("/sys/class/power_supply/BAT0/status")
+            .await,
+        Some("Charging".to_string())
+    );
+    assert_eq!(
+        root.read::<u8, _>("/sys/class/power_supply/BAT0/status")
+            .await,
+        None
+    );
+    assert_eq!(
+        root.read::<u8, _>("/sys/class/power_supply/BAT1/capacity")
+            .await,
+        None
+    );
+}
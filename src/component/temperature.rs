@@ -0,0 +1,178 @@
+use super::widget::Block;
+use super::Component;
+use super::SysRoot;
+use crate::config::TemperatureConfig;
+use async_trait::async_trait;
+use std::time::Duration;
+
+/// Directory of the hardware monitoring chips, named like `coretemp` or `nvme`
+const HWMON: &str = "/sys/class/hwmon";
+/// Directory of the thermal zones, named by `type` like `x86_pkg_temp`
+const THERMAL: &str = "/sys/class/thermal";
+
+/// A temperature reading in °C.
+#[derive(Debug, Clone, PartialEq)]
+struct Sensor {
+    /// Name of the hwmon chip, or type of the thermal zone
+    chip: String,
+    label: Option<String>,
+    celsius: f64,
+}
+
+impl Sensor {
+    /// Match `chip` or `chip/label`.
+    fn matches(&self, selector: &str) -> bool {
+        match selector.split_once('/') {
+            Some((chip, label)) => self.chip == chip && self.label.as_deref() == Some(label),
+            None => self.chip == selector,
+        }
+    }
+}
+
+/// Build a component to show the temperature from every `/sys/class/hwmon/*` chip and
+/// `/sys/class/thermal/thermal_zone*` zone under the [`SysRoot`].
+///
+/// Return None if no sensor matches.
+pub struct Temperature {
+    root: SysRoot,
+    config: TemperatureConfig,
+}
+
+impl Temperature {
+    pub fn new(config: &TemperatureConfig, root: &SysRoot) -> Self {
+        Self {
+            root: root.clone(),
+            config: config.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl Component for Temperature {
+    fn name(&self) -> &'static str {
+        "temperature"
+    }
+
+    fn interval(&self) -> Duration {
+        self.config.interval.or(10)
+    }
+
+    async fn update(&mut self) -> Option<Block> {
+        // the sensors are discovered on every update, as the devices like an external
+        // drive may come and go
+        let celsius = sensors(&self.root)
+            .await
+            .into_iter()
+            .filter(|sensor| match &self.config.sensor {
+                Some(selector) => sensor.matches(selector),
+                None => true,
+            })
+            .map(|sensor| sensor.celsius)
+            .reduce(f64::max)?;
+        Some(temperature_block(celsius, &self.config))
+    }
+}
+
+/// Every temperature of the hwmon chips and the thermal zones, which are reported in
+/// millidegree Celsius.
+async fn sensors(root: &SysRoot) -> Vec<Sensor> {
+    let mut sensors = Vec::new();
+
+    for dev in root.list(HWMON).await {
+        let dir = format!("{}/{}", HWMON, dev);
+        let chip: String = match root.read(format!("{}/name", dir)).await {
+            Some(chip) => chip,
+            None => continue,
+        };
+        for attr in root.list(&dir).await {
+            let input = match attr
+                .strip_prefix("temp")
+                .and_then(|attr| attr.strip_suffix("_input"))
+            {
+                Some(input) => input,
+                None => continue,
+            };
+            if let Some(milli) = root.read::<i64, _>(format!("{}/{}", dir, attr)).await {
+                sensors.push(Sensor {
+                    chip: chip.clone(),
+                    label: root.read(format!("{}/temp{}_label", dir, input)).await,
+                    celsius: milli as f64 / 1000.0,
+                });
+            }
+        }
+    }
+
+    for zone in root.list(THERMAL).await {
+        if !zone.starts_with("thermal_zone") {
+            continue;
+        }
+        let dir = format!("{}/{}", THERMAL, zone);
+        let (chip, milli) = tokio::join!(
+            root.read::<String, _>(format!("{}/type", dir)),
+            root.read::<i64, _>(format!("{}/temp", dir)),
+        );
+        if let (Some(chip), Some(milli)) = (chip, milli) {
+            sensors.push(Sensor {
+                chip,
+                label: None,
+                celsius: milli as f64 / 1000.0,
+            });
+        }
+    }
+
+    sensors
+}
+
+pub(super) fn temperature_block(celsius: f64, config: &TemperatureConfig) -> Block {
+    Block::new("", format!("{:.0}°C", celsius))
+        .color(&config.color)
+        .threshold(celsius, &config.thresholds)
+}
+
+#[tokio::test]
+async fn test_sensors() {
+    let root = SysRoot::fixture("temperature");
+    let sensors = sensors(&root).await;
+    let found: Vec<_> = sensors
+        .iter()
+        .map(|sensor| {
+            (
+                sensor.chip.as_str(),
+                sensor.label.as_deref(),
+                sensor.celsius,
+            )
+        })
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("acpitz", None, 27.8),
+            ("coretemp", Some("Package id 0"), 52.0),
+            ("coretemp", Some("Core 0"), 49.0),
+            ("nvme", Some("Composite"), 38.85),
+            ("x86_pkg_temp", None, 53.0),
+            ("acpitz", None, 27.8),
+        ]
+    );
+
+    let mut config = TemperatureConfig::default();
+    let mut temperature = Temperature::new(&config, &root);
+    assert_eq!(temperature.update().await.unwrap().text(), "53°C");
+
+    for (selector, text) in [
+        ("coretemp", "52°C"),
+        ("coretemp/Core 0", "49°C"),
+        ("nvme", "39°C"),
+    ] {
+        config.sensor = Some(selector.to_string());
+        let mut temperature = Temperature::new(&config, &root);
+        assert_eq!(temperature.update().await.unwrap().text(), text);
+    }
+
+    config.sensor = Some("k10temp".to_string());
+    assert!(Temperature::new(&config, &root).update().await.is_none());
+    assert!(Temperature::new(&config, &SysRoot::fixture("cpu"))
+        .update()
+        .await
+        .is_none());
+}
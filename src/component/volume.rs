@@ -2,28 +2,40 @@
 
 extern crate libpulse_binding as pulse;
 
-use std::{cell::RefCell, ops::Deref, rc::Rc};
+use std::{cell::Cell, cell::RefCell, ops::Deref, rc::Rc, thread, time::Duration};
 
 use super::widget::Block;
+use super::Component;
+use crate::config::BlockConfig;
+use async_trait::async_trait;
 use pulse::{
     callbacks::ListResult,
-    context::{introspect, Context as PulseContext},
+    context::{
+        introspect,
+        subscribe::{Facility, InterestMaskSet},
+        Context as PulseContext,
+    },
     mainloop::standard::{IterateResult, Mainloop},
     operation::{Operation, State},
     proplist::Proplist,
-    volume::ChannelVolumes,
+    volume::{ChannelVolumes, Volume as PulseVolume},
 };
 
 use anyhow::{anyhow, Context, Result};
+use tokio::sync::watch;
+use tokio::time::timeout;
+use tracing::{error, info};
 
 struct Device {
     volume: ChannelVolumes,
+    mute: bool,
 }
 
 impl<'a> From<&'a introspect::SinkInfo<'a>> for Device {
     fn from(item: &'a introspect::SinkInfo<'a>) -> Self {
         Self {
             volume: item.volume,
+            mute: item.mute,
         }
     }
 }
@@ -78,11 +90,11 @@ impl SinkController {
         loop {
             match mainloop.borrow_mut().iterate(false) {
                 IterateResult::Err(e) => {
-                    eprintln!("Fail to iterate mainloop");
+                    error!("Fail to iterate mainloop: {}", e);
                     return Err(anyhow!(e));
                 }
                 IterateResult::Quit(_) => {
-                    eprintln!("Fail to iterate mainloop, without error...");
+                    error!("Fail to iterate mainloop, without error...");
                     return Err(anyhow!("Mainloop iterate quit without an error"));
                 }
                 _ => {}
@@ -92,7 +104,7 @@ impl SinkController {
             match context.borrow_mut().get_state() {
                 Ready => break,
                 Failed | Terminated => {
-                    eprintln!("Connection failed, or terminated");
+                    error!("Connection failed, or terminated");
                     return Err(anyhow!(
                         "Connection context failed or terminated without error"
                     ));
@@ -132,6 +144,48 @@ impl SinkController {
         Ok(())
     }
 
+    /// Subscribe to the sink and server events. The returned flag is set when
+    /// any of the sinks or the default sink is changed.
+    fn subscribe(&mut self) -> Result<Rc<Cell<bool>>> {
+        let changed = Rc::new(Cell::new(true));
+        let r_changed = changed.clone();
+        self.context
+            .borrow_mut()
+            .set_subscribe_callback(Some(Box::new(move |facility, _, _| {
+                if matches!(facility, Some(Facility::Sink) | Some(Facility::Server)) {
+                    r_changed.set(true);
+                }
+            })));
+
+        let op = self
+            .context
+            .borrow_mut()
+            .subscribe(InterestMaskSet::SINK | InterestMaskSet::SERVER, |_| {});
+        self.wait_for_operation(op)?;
+
+        Ok(changed)
+    }
+
+    /// Block the current thread until the `changed` flag is set.
+    fn wait_for_change(&mut self, changed: &Cell<bool>) -> Result<()> {
+        while !changed.get() {
+            match self.mainloop.borrow_mut().iterate(true) {
+                IterateResult::Err(e) => return Err(e.into()),
+                IterateResult::Success(_) => {}
+                IterateResult::Quit(_) => {
+                    return Err(anyhow!("mainloop quit without an error"));
+                }
+            }
+
+            use pulse::context::State::{Failed, Terminated};
+            if let Failed | Terminated = self.context.borrow().get_state() {
+                return Err(anyhow!("Connection to PulseAudio failed or terminated"));
+            }
+        }
+        changed.set(false);
+        Ok(())
+    }
+
     fn get_server_info(&mut self) -> Result<Server> {
         let server = Rc::new(RefCell::new(Some(None)));
         let r_server = server.clone();
@@ -177,25 +231,126 @@ impl SinkController {
 }
 
 #[test]
-fn test() {
-    let mut s = SinkController::new().unwrap();
-    let dev = s.get_default_device();
-    dbg!(dev.unwrap().volume.print());
+fn test_volume_percent() {
+    let normal = PulseVolume::NORMAL.0;
+    assert_eq!(volume_percent(PulseVolume::NORMAL), 100);
+    assert_eq!(volume_percent(PulseVolume::MUTED), 0);
+    assert_eq!(volume_percent(PulseVolume(normal / 2)), 50);
+    // 99.6 %
+    assert_eq!(volume_percent(PulseVolume(normal * 996 / 1000)), 100);
+    assert_eq!(volume_percent(PulseVolume(normal * 3 / 2)), 150);
+}
+
+#[test]
+#[ignore = "needs PulseAudio"]
+fn test_default_device() {
+    let mut controller = SinkController::new().unwrap();
+    let dev = controller.get_default_device().unwrap();
+    assert!(sound_volume(&dev, &BlockConfig::default()).is_some());
+}
+
+/// Create a sound volume component for bar. A long-lived connection to PulseAudio
+/// is held in a dedicated thread, and the block is updated when the volume, mute
+/// state or the default sink is changed.
+pub struct Volume {
+    content: watch::Receiver<Option<Block>>,
+    initialized: bool,
 }
 
-/// Create a sound volume component for bar
-pub async fn sound_volume() -> Option<Block> {
-    let mut s = SinkController::new().ok()?;
-    let dev = s.get_default_device().ok()?;
+impl Volume {
+    pub fn new(config: &BlockConfig) -> Self {
+        let (tx, rx) = watch::channel(None);
+        let config = config.clone();
+        thread::spawn(move || watch_volume(tx, config));
+
+        Self {
+            content: rx,
+            initialized: false,
+        }
+    }
+}
+
+#[async_trait]
+impl Component for Volume {
+    fn name(&self) -> &'static str {
+        "volume"
+    }
+
+    async fn update(&mut self) -> Option<Block> {
+        if !self.initialized {
+            // give the connection a chance to get ready before the first draw
+            self.initialized = true;
+            let _ = timeout(Duration::from_secs(1), self.content.changed()).await;
+        }
+        self.content.borrow().clone()
+    }
+
+    async fn wait(&mut self) {
+        if self.content.changed().await.is_err() {
+            // the watcher thread is gone, nothing will be changed anymore
+            futures::future::pending::<()>().await;
+        }
+    }
+}
+
+/// Keep pushing the latest volume into `tx`. Reconnect to PulseAudio after the
+/// interval set in configuration if the connection get lost.
+fn watch_volume(tx: watch::Sender<Option<Block>>, config: BlockConfig) {
+    loop {
+        if let Err(e) = listen(&tx, &config) {
+            error!("Lost connection to PulseAudio: {:?}", e);
+        }
+
+        if tx.send(None).is_err() {
+            return;
+        }
+        thread::sleep(config.interval.or(10));
+        info!("Reconnecting to PulseAudio");
+    }
+}
+
+fn listen(tx: &watch::Sender<Option<Block>>, config: &BlockConfig) -> Result<()> {
+    let mut controller = SinkController::new()?;
+    let changed = controller.subscribe()?;
+
+    loop {
+        controller.wait_for_change(&changed)?;
+
+        let block = controller
+            .get_default_device()
+            .ok()
+            .and_then(|dev| sound_volume(&dev, config));
+        if tx.send(block).is_err() {
+            // the component is dropped
+            return Ok(());
+        }
+    }
+}
+
+fn sound_volume(dev: &Device, config: &BlockConfig) -> Option<Block> {
     let devices = dev.volume.get();
     if devices.is_empty() {
         return None;
     }
-    let dev = devices[0];
-    let icon = if dev.is_muted() { "" } else { "" };
-    Some(
-        Block::new(icon, dev.print().trim())
-            .text_fg("#EAEAEA")
-            .icon_fg("#EAEAEA"),
-    )
+    let vol = devices[0];
+    Some(volume_block(
+        volume_percent(vol),
+        dev.mute || vol.is_muted(),
+        config,
+    ))
+}
+
+/// Rounded in the same way as `pa_volume_snprint`.
+fn volume_percent(vol: PulseVolume) -> u64 {
+    let normal = PulseVolume::NORMAL.0 as u64;
+    (vol.0 as u64 * 100 + normal / 2) / normal
+}
+
+/// A muted sink counts as 0 for the thresholds.
+pub(super) fn volume_block(percent: u64, muted: bool, config: &BlockConfig) -> Block {
+    let icon = if muted { "" } else { "" };
+    let value = if muted { 0.0 } else { percent as f64 };
+    Block::new(icon, format!("{}%", percent))
+        .color(&config.color)
+        .threshold(value, &config.thresholds)
 }
@@ -1,12 +1,21 @@
 use super::color::Color;
+use crate::config::{ColorConfig, Threshold};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Block {
     color: Color,
     text: String,
     icon: String,
+    urgent: bool,
+    /// A matching threshold set some colors
+    threshold_color: bool,
+    /// Values from 0 to 1, drawn as a bar graph after the text
+    graph: Vec<f32>,
 }
 
+/// Unicode blocks from the lowest to the highest bar
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
 impl Block {
     /// Builder chain for component.
     pub fn new<T: Into<String>, E: Into<String>>(icon: T, text: E) -> Self {
@@ -14,43 +23,160 @@ impl Block {
             icon: icon.into(),
             text: text.into(),
             color: Color::new(),
+            urgent: false,
+            threshold_color: false,
+            graph: Vec::new(),
         }
     }
 
-    pub fn text_fg<T: Into<String>>(mut self, fg: T) -> Self {
-        self.color = self.color.text_fg(fg);
-        self
+    pub fn icon(&self) -> &str {
+        &self.icon
     }
 
-    pub fn text_color<T: Into<String>>(mut self, fg: T, bg: T) -> Self {
-        self.color = self.color.text(fg, bg);
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn colors(&self) -> &Color {
+        &self.color
+    }
+
+    pub fn graph_values(&self) -> &[f32] {
+        &self.graph
+    }
+
+    /// The graph in Unicode block characters, for the outputs without drawing support.
+    pub fn sparkline(&self) -> String {
+        self.graph
+            .iter()
+            .map(|value| {
+                let level = (value.clamp(0.0, 1.0) * (SPARKS.len() - 1) as f32).round();
+                SPARKS[level as usize]
+            })
+            .collect()
+    }
+
+    /// Whether the block needs attention, each output shows it in its own way.
+    pub fn is_urgent(&self) -> bool {
+        self.urgent
+    }
+
+    /// Whether the colors are changed by a threshold, the outputs should keep them even
+    /// if the block is urgent.
+    pub fn has_threshold_color(&self) -> bool {
+        self.threshold_color
+    }
+
+    /// Apply the colors set in configuration. Unset colors are left untouched.
+    pub fn color(mut self, config: &ColorConfig) -> Self {
+        if let Some(fg) = &config.text_fg {
+            self.color = self.color.text_fg(*fg);
+        }
+        if let Some(bg) = &config.text_bg {
+            self.color = self.color.text_bg(*bg);
+        }
+        if let Some(fg) = &config.icon_fg {
+            self.color = self.color.icon_fg(*fg);
+        }
+        if let Some(bg) = &config.icon_bg {
+            self.color = self.color.icon_bg(*bg);
+        }
         self
     }
 
-    pub fn icon_color<T: Into<String>>(mut self, fg: T, bg: T) -> Self {
-        self.color = self.color.icon(fg, bg);
+    /// Draw the values from 0 to 1 as a bar graph after the text.
+    pub fn graph(mut self, values: Vec<f32>) -> Self {
+        self.graph = values;
         self
     }
 
-    pub fn icon_fg<T: Into<String>>(mut self, fg: T) -> Self {
-        self.color = self.color.icon_fg(fg);
+    /// Apply the colors and the urgency of every threshold matching the value.
+    pub fn threshold(mut self, value: f64, thresholds: &[Threshold]) -> Self {
+        for threshold in thresholds.iter().filter(|t| t.matches(value)) {
+            self = self.color(&threshold.color);
+            self.urgent |= threshold.urgent;
+            self.threshold_color |= threshold.color != ColorConfig::default();
+        }
         self
     }
 }
 
 impl std::fmt::Display for Block {
+    /// Plain text without colors, output backends render the colors in their own syntax.
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let s = String::new();
-
-        let fg = self.color.fg.as_ref().unwrap_or(&s);
-        let bg = self.color.bg.as_ref().unwrap_or(&s);
-        let icon_fg = self.color.icon_fg.as_ref().unwrap_or(&s);
-        let icon_bg = self.color.icon_bg.as_ref().unwrap_or(&s);
-        // [icon] [text]
-        write!(
-            f,
-            "{}{}{} {}{}{}",
-            icon_fg, icon_bg, self.icon, fg, bg, self.text
-        )
+        // [icon] [text] [graph]
+        write!(f, "{} {}", self.icon, self.text)?;
+        if !self.graph.is_empty() {
+            write!(f, " {}", self.sparkline())?;
+        }
+        Ok(())
     }
 }
+
+/// Size in GiB with a decimal, like `7.8 GiB`.
+pub(super) fn format_gib(bytes: u64) -> String {
+    format!("{:.1} GiB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+}
+
+#[test]
+fn test_block_display() {
+    use super::Rgba;
+
+    let block = Block::new("A", "text");
+    assert_eq!(block.to_string(), "A text");
+    assert_eq!(block.colors(), &Color::new());
+
+    let white = Rgba::rgb(0xFF, 0xFF, 0xFF);
+    let block = Block::new("", "").color(&ColorConfig {
+        text_fg: Some(white),
+        ..Default::default()
+    });
+    assert_eq!(block.to_string(), " ");
+    assert_eq!(block.colors(), &Color::new().text_fg(white));
+}
+
+#[test]
+fn test_block_graph() {
+    let block = Block::new("C", "50 %").graph(vec![0.0, 0.3, 0.5, 1.0, 1.5]);
+    assert_eq!(block.sparkline(), "▁▃▅██");
+    assert_eq!(block.to_string(), "C 50 % ▁▃▅██");
+}
+
+#[test]
+fn test_block_threshold() {
+    use super::Rgba;
+
+    let red = Rgba::rgb(0xBF, 0x61, 0x6A);
+    let orange = Rgba::rgb(0xD0, 0x87, 0x70);
+    let thresholds = [
+        Threshold {
+            below: Some(20.0),
+            color: ColorConfig {
+                text_fg: Some(orange),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        Threshold {
+            below: Some(5.0),
+            color: ColorConfig {
+                text_bg: Some(red),
+                ..Default::default()
+            },
+            urgent: true,
+            ..Default::default()
+        },
+    ];
+
+    let block = Block::new("B", "50 %").threshold(50.0, &thresholds);
+    assert_eq!(block.colors(), &Color::new());
+    assert!(!block.is_urgent());
+
+    let block = Block::new("B", "10 %").threshold(10.0, &thresholds);
+    assert_eq!(block.colors(), &Color::new().text_fg(orange));
+    assert!(!block.is_urgent());
+
+    let block = Block::new("B", "3 %").threshold(3.0, &thresholds);
+    assert_eq!(block.colors(), &Color::new().text_fg(orange).text_bg(red));
+    assert!(block.is_urgent());
+}
@@ -0,0 +1,433 @@
+use super::widget::Block;
+use super::Component;
+use crate::config::BlockConfig;
+use anyhow::Result;
+use async_trait::async_trait;
+use dbus::arg;
+use dbus::message::MatchRule;
+use dbus::nonblock::{stdintf::org_freedesktop_dbus::Properties, MsgMatch, Proxy, SyncConnection};
+use dbus::{Message, Path};
+use futures::channel::mpsc::UnboundedReceiver;
+use futures::stream::{self, Select, StreamExt};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::error;
+
+const NM: &str = "org.freedesktop.NetworkManager";
+const NM_PATH: &str = "/org/freedesktop/NetworkManager";
+const ACTIVE: &str = "org.freedesktop.NetworkManager.Connection.Active";
+const ACCESS_POINT: &str = "org.freedesktop.NetworkManager.AccessPoint";
+const WIRELESS: &str = "802-11-wireless";
+
+/// The primary network connection.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Connection {
+    Disconnected,
+    Wifi {
+        ssid: String,
+        strength: u8,
+    },
+    /// Connections other than Wi-Fi, like ethernet, shown by the name
+    Other {
+        id: String,
+    },
+}
+
+/// Build a component to show the Wi-Fi SSID and signal strength from NetworkManager.
+/// It is updated on the NetworkManager signals, and polls on the interval when the
+/// signals are unavailable.
+pub struct Wifi {
+    conn: Arc<SyncConnection>,
+    config: BlockConfig,
+    /// Signals from NetworkManager, None if not subscribed yet
+    signals: Option<Select<UnboundedReceiver<Message>, UnboundedReceiver<Message>>>,
+    /// Access point of the Wi-Fi connection in the last update
+    access_point: Option<Path<'static>>,
+    // hold the matches to keep receiving signals
+    _matches: Vec<MsgMatch>,
+}
+
+impl Wifi {
+    pub fn new(config: &BlockConfig, conn: Arc<SyncConnection>) -> Self {
+        Self {
+            conn,
+            config: config.clone(),
+            signals: None,
+            access_point: None,
+            _matches: Vec::new(),
+        }
+    }
+
+    /// Subscribe to the `StateChanged` signal of NetworkManager, and the
+    /// `PropertiesChanged` signal of its objects for the connections and signal
+    /// strength.
+    async fn subscribe(&mut self) -> Result<()> {
+        let state = MatchRule::new_signal(NM, "StateChanged").with_sender(NM);
+        let (state, state_stream) = self.conn.add_match(state).await?.msg_stream();
+
+        let properties =
+            MatchRule::new_signal("org.freedesktop.DBus.Properties", "PropertiesChanged")
+                .with_sender(NM)
+                .with_namespaced_path(NM_PATH);
+        let (properties, properties_stream) = self.conn.add_match(properties).await?.msg_stream();
+
+        self.signals = Some(stream::select(state_stream, properties_stream));
+        self._matches = vec![state, properties];
+        Ok(())
+    }
+
+    async fn get<R: for<'b> arg::Get<'b> + 'static>(
+        &self,
+        path: &Path<'static>,
+        interface: &str,
+        name: &str,
+    ) -> Result<R> {
+        let proxy = Proxy::new(
+            NM,
+            path.clone(),
+            Duration::from_millis(2000),
+            self.conn.clone(),
+        );
+        Ok(proxy.get(interface, name).await?)
+    }
+
+    /// The primary connection, and whether a VPN is active.
+    async fn connection(&mut self) -> Result<(Connection, bool)> {
+        self.access_point = None;
+        let nm = Path::from(NM_PATH);
+        let primary: Path = self.get(&nm, NM, "PrimaryConnection").await?;
+        if &*primary == "/" {
+            return Ok((Connection::Disconnected, false));
+        }
+
+        let paths: Vec<Path> = self.get(&nm, NM, "ActiveConnections").await?;
+        let mut active = Vec::with_capacity(paths.len());
+        for path in paths {
+            active.push(ActiveConnection {
+                kind: self.get(&path, ACTIVE, "Type").await?,
+                vpn: self.get(&path, ACTIVE, "Vpn").await?,
+                path,
+            });
+        }
+        let (wifi, vpn) = summarize(&active);
+
+        let connection = match wifi {
+            Some(path) => {
+                let ap: Path = self.get(path, ACTIVE, "SpecificObject").await?;
+                let ssid: Vec<u8> = self.get(&ap, ACCESS_POINT, "Ssid").await?;
+                let strength = self.get(&ap, ACCESS_POINT, "Strength").await?;
+                self.access_point = Some(ap);
+                Connection::Wifi {
+                    ssid: String::from_utf8_lossy(&ssid).into_owned(),
+                    strength,
+                }
+            }
+            None => Connection::Other {
+                id: self.get(&primary, ACTIVE, "Id").await?,
+            },
+        };
+        Ok((connection, vpn))
+    }
+}
+
+#[async_trait]
+impl Component for Wifi {
+    fn name(&self) -> &'static str {
+        "wifi"
+    }
+
+    fn interval(&self) -> Duration {
+        self.config.interval.or(60)
+    }
+
+    async fn update(&mut self) -> Option<Block> {
+        if self.signals.is_none() {
+            if let Err(e) = self.subscribe().await {
+                error!(
+                    "Fail to subscribe to NetworkManager signals, fallback to polling: {}",
+                    e
+                );
+            }
+        }
+
+        // hide the block when NetworkManager is not running
+        let (connection, vpn) = self
+            .connection()
+            .await
+            .map_err(|e| error!("Fail to get the connection from NetworkManager: {}", e))
+            .ok()?;
+        Some(wifi_block(&connection, vpn, &self.config))
+    }
+
+    async fn wait(&mut self) {
+        while let Some(signals) = self.signals.as_mut() {
+            match signals.next().await {
+                Some(msg) => {
+                    if is_relevant(&msg, self.access_point.as_ref()) {
+                        return;
+                    }
+                }
+                // the stream ends when the connection is lost
+                None => self.signals = None,
+            }
+        }
+        tokio::time::sleep(self.interval()).await;
+    }
+}
+
+/// An active connection of NetworkManager.
+struct ActiveConnection {
+    path: Path<'static>,
+    /// Type of the connection settings, like `802-11-wireless` or `wireguard`
+    kind: String,
+    vpn: bool,
+}
+
+/// The first Wi-Fi connection, and whether a VPN is active. WireGuard connections are
+/// not flagged as VPN by NetworkManager.
+fn summarize(active: &[ActiveConnection]) -> (Option<&Path<'static>>, bool) {
+    let wifi = active.iter().find(|conn| conn.kind == WIRELESS);
+    let vpn = active
+        .iter()
+        .any(|conn| conn.vpn || conn.kind == "wireguard");
+    (wifi.map(|conn| &conn.path), vpn)
+}
+
+/// Return true if the signal may change the connection. The access points other than
+/// the connected one are ignored, their signal strength changes all the time.
+fn is_relevant(msg: &Message, access_point: Option<&Path>) -> bool {
+    match msg.member().as_deref() {
+        Some("StateChanged") => true,
+        Some("PropertiesChanged") => match msg.read1::<&str>() {
+            Ok(NM | ACTIVE) => true,
+            Ok(ACCESS_POINT) => match (msg.path(), access_point) {
+                (Some(path), Some(ap)) => *path == **ap,
+                _ => false,
+            },
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+pub(super) fn wifi_block(connection: &Connection, vpn: bool, config: &BlockConfig) -> Block {
+    let mut text = match connection {
+        Connection::Disconnected => "disconnected".to_string(),
+        Connection::Wifi { ssid, strength } => format!("{} {}%", ssid, strength),
+        Connection::Other { id } => id.clone(),
+    };
+    if vpn {
+        text.push_str(" VPN");
+    }
+
+    let block = Block::new("", text).color(&config.color);
+    match connection {
+        Connection::Wifi { strength, .. } => block.threshold(*strength as f64, &config.thresholds),
+        _ => block,
+    }
+}
+
+/// Property values by the object path and the property name
+#[cfg(test)]
+type MockProperties =
+    std::collections::HashMap<(String, String), Box<dyn arg::RefArg + Send + Sync>>;
+
+/// NetworkManager stand-in serving the properties set by [`MockNetworkManager::set`].
+#[cfg(test)]
+struct MockNetworkManager {
+    conn: Arc<SyncConnection>,
+    properties: Arc<std::sync::Mutex<MockProperties>>,
+}
+
+#[cfg(test)]
+impl MockNetworkManager {
+    async fn start(conn: Arc<SyncConnection>) -> Self {
+        use dbus::channel::{MatchingReceiver, Sender};
+
+        conn.request_name(NM, false, true, true).await.unwrap();
+
+        let mock = Self {
+            conn: conn.clone(),
+            properties: Default::default(),
+        };
+        let properties = mock.properties.clone();
+        let rule = MatchRule::new_method_call()
+            .with_interface("org.freedesktop.DBus.Properties")
+            .with_member("Get");
+        conn.start_receive(
+            rule,
+            Box::new(move |msg, conn| {
+                let (_, name): (&str, &str) = msg.read2().unwrap();
+                let key = (msg.path().unwrap().to_string(), name.to_string());
+                let reply = match properties.lock().unwrap().get(&key) {
+                    Some(value) => msg.method_return().append1(arg::Variant(value.box_clone())),
+                    None => Message::error(
+                        &msg,
+                        &"org.freedesktop.DBus.Error.UnknownProperty".into(),
+                        &std::ffi::CString::new(name).unwrap(),
+                    ),
+                };
+                let _ = conn.send(reply);
+                true
+            }),
+        );
+        mock
+    }
+
+    fn set<V: arg::RefArg + Send + Sync + 'static>(&self, path: &str, name: &str, value: V) {
+        self.properties
+            .lock()
+            .unwrap()
+            .insert((path.to_string(), name.to_string()), Box::new(value));
+    }
+
+    fn changed(&self, path: &str, interface: &str) {
+        use dbus::channel::Sender;
+
+        self.conn.send(properties_changed(path, interface)).unwrap();
+    }
+
+    fn state_changed(&self, state: u32) {
+        use dbus::channel::Sender;
+
+        let signal = Message::signal(&Path::from(NM_PATH), &NM.into(), &"StateChanged".into())
+            .append1(state);
+        self.conn.send(signal).unwrap();
+    }
+}
+
+/// `PropertiesChanged` without the values, which is only used as a trigger.
+#[cfg(test)]
+fn properties_changed(path: &str, interface: &str) -> Message {
+    let values: std::collections::HashMap<&str, arg::Variant<u8>> = Default::default();
+    let invalidated: Vec<&str> = Vec::new();
+    Message::signal(
+        &Path::from(path),
+        &"org.freedesktop.DBus.Properties".into(),
+        &"PropertiesChanged".into(),
+    )
+    .append3(interface, values, invalidated)
+}
+
+#[test]
+fn test_relevant_signals() {
+    const AP: &str = "/org/freedesktop/NetworkManager/AccessPoint/7";
+    let ap = Path::from(AP);
+
+    let state =
+        Message::signal(&Path::from(NM_PATH), &NM.into(), &"StateChanged".into()).append1(20u32);
+    assert!(is_relevant(&state, None));
+    assert!(is_relevant(&properties_changed(NM_PATH, NM), None));
+    assert!(is_relevant(
+        &properties_changed("/org/freedesktop/NetworkManager/ActiveConnection/1", ACTIVE),
+        None
+    ));
+
+    let connected = properties_changed(AP, ACCESS_POINT);
+    assert!(is_relevant(&connected, Some(&ap)));
+    assert!(!is_relevant(&connected, None));
+    let nearby = properties_changed(
+        "/org/freedesktop/NetworkManager/AccessPoint/8",
+        ACCESS_POINT,
+    );
+    assert!(!is_relevant(&nearby, Some(&ap)));
+    let device = properties_changed(
+        "/org/freedesktop/NetworkManager/Devices/2",
+        "org.freedesktop.NetworkManager.Device",
+    );
+    assert!(!is_relevant(&device, Some(&ap)));
+}
+
+#[test]
+fn test_summarize() {
+    let active = |path: &str, kind: &str, vpn| ActiveConnection {
+        path: Path::from(path.to_string()),
+        kind: kind.to_string(),
+        vpn,
+    };
+
+    assert_eq!(summarize(&[]), (None, false));
+
+    let connections = [
+        active("/ethernet", "802-3-ethernet", false),
+        active("/home", WIRELESS, false),
+        active("/phone", WIRELESS, false),
+    ];
+    assert_eq!(summarize(&connections), (Some(&Path::from("/home")), false));
+
+    let connections = [
+        active("/ethernet", "802-3-ethernet", false),
+        active("/wg0", "wireguard", false),
+    ];
+    assert_eq!(summarize(&connections), (None, true));
+
+    let connections = [
+        active("/home", WIRELESS, false),
+        active("/work", "vpn", true),
+    ];
+    assert_eq!(summarize(&connections), (Some(&Path::from("/home")), true));
+}
+
+#[tokio::test]
+#[ignore = "needs dbus-daemon"]
+async fn test_wifi_signals() {
+    use super::bus::PrivateBus;
+
+    const AC1: &str = "/org/freedesktop/NetworkManager/ActiveConnection/1";
+    const AC2: &str = "/org/freedesktop/NetworkManager/ActiveConnection/2";
+    const AP: &str = "/org/freedesktop/NetworkManager/AccessPoint/7";
+
+    let bus = PrivateBus::start().expect("dbus-daemon is not installed");
+    let mut wifi = Wifi::new(&BlockConfig::default(), bus.connect());
+    // NetworkManager is not running
+    assert!(wifi.update().await.is_none());
+
+    let nm = MockNetworkManager::start(bus.connect()).await;
+    nm.set(NM_PATH, "PrimaryConnection", Path::from(AC1));
+    nm.set(NM_PATH, "ActiveConnections", vec![Path::from(AC1)]);
+    nm.set(AC1, "Type", WIRELESS.to_string());
+    nm.set(AC1, "Id", "home".to_string());
+    nm.set(AC1, "Vpn", false);
+    nm.set(AC1, "SpecificObject", Path::from(AP));
+    nm.set(AP, "Ssid", b"home".to_vec());
+    nm.set(AP, "Strength", 72u8);
+    assert_eq!(wifi.update().await.unwrap().text(), "home 72%");
+
+    // nearby access points are ignored
+    nm.changed(
+        "/org/freedesktop/NetworkManager/AccessPoint/8",
+        ACCESS_POINT,
+    );
+    let ignored = tokio::time::timeout(Duration::from_millis(200), wifi.wait()).await;
+    assert!(ignored.is_err());
+
+    // the signal strength changes
+    nm.set(AP, "Strength", 40u8);
+    nm.changed(AP, ACCESS_POINT);
+    tokio::time::timeout(Duration::from_secs(2), wifi.wait())
+        .await
+        .unwrap();
+    assert_eq!(wifi.update().await.unwrap().text(), "home 40%");
+
+    // a VPN connects
+    nm.set(
+        NM_PATH,
+        "ActiveConnections",
+        vec![Path::from(AC1), Path::from(AC2)],
+    );
+    nm.set(AC2, "Type", "vpn".to_string());
+    nm.set(AC2, "Vpn", true);
+    nm.changed(NM_PATH, NM);
+    tokio::time::timeout(Duration::from_secs(2), wifi.wait())
+        .await
+        .unwrap();
+    assert_eq!(wifi.update().await.unwrap().text(), "home 40% VPN");
+
+    nm.set(NM_PATH, "PrimaryConnection", Path::from("/"));
+    nm.set(NM_PATH, "ActiveConnections", Vec::<Path>::new());
+    nm.state_changed(20);
+    tokio::time::timeout(Duration::from_secs(2), wifi.wait())
+        .await
+        .unwrap();
+    assert_eq!(wifi.update().await.unwrap().text(), "disconnected");
+}
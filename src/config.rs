@@ -0,0 +1,697 @@
+use crate::component::Rgba;
+use anyhow::{bail, Context, Result};
+use chrono::format::{Item, StrftimeItems};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// All the available blocks that can be put on the bar.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockKind {
+    Song,
+    Volume,
+    HeadsetBattery,
+    Battery,
+    Cpu,
+    CpuFreq,
+    Memory,
+    Disk,
+    Network,
+    Wifi,
+    Ip,
+    Temperature,
+    Date,
+}
+
+/// Configuration for the whole bar. Read from `~/.config/dwm-bar/config.toml` by default.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Text put between two blocks
+    pub divider: String,
+    /// Text put after each block to reset the colors, only used by the dwm output
+    pub reset: String,
+    pub urgent: UrgentColor,
+    /// Blocks to be shown, from left to right
+    pub blocks: Vec<BlockKind>,
+
+    pub song: SongConfig,
+    pub volume: BlockConfig,
+    pub headset_battery: BlockConfig,
+    pub battery: BatteryConfig,
+    pub cpu: CpuConfig,
+    pub cpu_freq: CpuFreqConfig,
+    pub memory: MemoryConfig,
+    pub disk: DiskConfig,
+    pub network: NetworkConfig,
+    pub wifi: BlockConfig,
+    pub ip: IpConfig,
+    pub temperature: TemperatureConfig,
+    pub date: DateConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            divider: "     |     ".to_string(),
+            // Reset the color the SchemeNorm
+            reset: "^d^".to_string(),
+            urgent: UrgentColor::default(),
+            blocks: vec![
+                BlockKind::Song,
+                BlockKind::Volume,
+                #[cfg(feature = "bluetooth-battery")]
+                BlockKind::HeadsetBattery,
+                BlockKind::Battery,
+                BlockKind::Cpu,
+                BlockKind::Date,
+            ],
+            song: SongConfig::default(),
+            volume: BlockConfig::default(),
+            headset_battery: BlockConfig::default(),
+            battery: BatteryConfig::default(),
+            cpu: CpuConfig::default(),
+            cpu_freq: CpuFreqConfig::default(),
+            memory: MemoryConfig::default(),
+            disk: DiskConfig::default(),
+            network: NetworkConfig::default(),
+            wifi: BlockConfig::default(),
+            ip: IpConfig::default(),
+            temperature: TemperatureConfig::default(),
+            date: DateConfig::default(),
+        }
+    }
+}
+
+/// Colors of a block. Each color should be a hex code like `#RRGGBB` or `#RRGGBBAA`.
+/// Unset colors fallback to the default color scheme of the bar.
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColorConfig {
+    pub text_fg: Option<Rgba>,
+    pub text_bg: Option<Rgba>,
+    pub icon_fg: Option<Rgba>,
+    pub icon_bg: Option<Rgba>,
+}
+
+impl ColorConfig {
+    fn foreground(fg: Rgba) -> Self {
+        Self {
+            text_fg: Some(fg),
+            icon_fg: Some(fg),
+            ..Default::default()
+        }
+    }
+}
+
+/// Colors of the urgent blocks in the dwm output, which has no urgency marker. Only
+/// used when no matching threshold sets a color.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct UrgentColor {
+    pub fg: Rgba,
+    pub bg: Rgba,
+}
+
+impl Default for UrgentColor {
+    fn default() -> Self {
+        Self {
+            fg: FOREGROUND,
+            bg: Rgba::rgb(0xBF, 0x61, 0x6A),
+        }
+    }
+}
+
+/// Change the block when its value is above or below the limits, like showing the
+/// battery in red below 20%. When both limits are set, the value must be within them.
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Threshold {
+    pub above: Option<f64>,
+    pub below: Option<f64>,
+    /// Colors to override the block colors
+    pub color: ColorConfig,
+    /// Ask the output to draw attention to the block
+    pub urgent: bool,
+}
+
+impl Threshold {
+    pub fn matches(&self, value: f64) -> bool {
+        let above = self.above.unwrap_or(f64::NEG_INFINITY);
+        let below = self.below.unwrap_or(f64::INFINITY);
+        value > above && value < below
+    }
+}
+
+/// Refresh interval in seconds, each block has its own default value.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(transparent)]
+pub struct Interval(Option<u64>);
+
+impl Interval {
+    /// The configured interval, or the given `default` seconds if unset.
+    pub fn or(self, default: u64) -> Duration {
+        Duration::from_secs(self.0.unwrap_or(default))
+    }
+}
+
+/// Check the settings shared by the blocks, the blocks without thresholds pass an
+/// empty slice.
+fn validate_block(block: &str, interval: Interval, thresholds: &[Threshold]) -> Result<()> {
+    if interval.0 == Some(0) {
+        bail!("`{}.interval` should be greater than 0", block);
+    }
+    validate_thresholds(thresholds, block)
+}
+
+fn validate_thresholds(thresholds: &[Threshold], block: &str) -> Result<()> {
+    if thresholds
+        .iter()
+        .any(|threshold| threshold.above.is_none() && threshold.below.is_none())
+    {
+        bail!("every `{}.thresholds` needs `above` or `below`", block);
+    }
+    Ok(())
+}
+
+/// Default foreground color of all the blocks
+const FOREGROUND: Rgba = Rgba::rgb(0xEA, 0xEA, 0xEA);
+
+/// Settings shared by every block.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BlockConfig {
+    pub interval: Interval,
+    pub color: ColorConfig,
+    /// Applied in order, later thresholds override the earlier ones
+    pub thresholds: Vec<Threshold>,
+}
+
+impl Default for BlockConfig {
+    fn default() -> Self {
+        Self {
+            interval: Interval::default(),
+            color: ColorConfig::foreground(FOREGROUND),
+            thresholds: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SongConfig {
+    pub interval: Interval,
+    /// Maximum characters of the song information, longer text will be trimmed
+    pub max_length: usize,
+    pub color: ColorConfig,
+}
+
+impl Default for SongConfig {
+    fn default() -> Self {
+        Self {
+            interval: Interval::default(),
+            max_length: 40,
+            color: ColorConfig {
+                text_fg: Some(FOREGROUND),
+                text_bg: Some(Rgba::rgb(0x17, 0x16, 0x17)),
+                icon_fg: Some(FOREGROUND),
+                icon_bg: Some(Rgba::rgb(0x0C, 0x0C, 0x0C)),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct CpuConfig {
+    pub interval: Interval,
+    /// Draw the usage of every core as a bar graph after the average
+    pub per_core: bool,
+    /// Number of recent samples drawn as a graph after the average, 0 to disable
+    pub history: usize,
+    pub color: ColorConfig,
+    pub thresholds: Vec<Threshold>,
+}
+
+impl Default for CpuConfig {
+    fn default() -> Self {
+        Self {
+            interval: Interval::default(),
+            per_core: false,
+            history: 0,
+            color: ColorConfig::foreground(FOREGROUND),
+            thresholds: Vec::new(),
+        }
+    }
+}
+
+/// Which frequency to show when the cores run at different frequencies.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FreqMode {
+    Average,
+    Max,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct CpuFreqConfig {
+    pub interval: Interval,
+    pub mode: FreqMode,
+    pub color: ColorConfig,
+    /// Limits in GHz
+    pub thresholds: Vec<Threshold>,
+}
+
+impl Default for CpuFreqConfig {
+    fn default() -> Self {
+        Self {
+            interval: Interval::default(),
+            mode: FreqMode::Average,
+            color: ColorConfig::foreground(FOREGROUND),
+            thresholds: Vec::new(),
+        }
+    }
+}
+
+/// Placeholders available in `memory.format`
+pub const MEMORY_PLACEHOLDERS: [&str; 6] = [
+    "mem_percent",
+    "mem_used",
+    "mem_total",
+    "swap_percent",
+    "swap_used",
+    "swap_total",
+];
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct MemoryConfig {
+    pub interval: Interval,
+    /// Text with placeholders like `{mem_used}`, see [`MEMORY_PLACEHOLDERS`]
+    pub format: String,
+    pub color: ColorConfig,
+    /// Limits of the memory usage in percentage
+    pub thresholds: Vec<Threshold>,
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            interval: Interval::default(),
+            format: "{mem_percent} %".to_string(),
+            color: ColorConfig::foreground(FOREGROUND),
+            thresholds: Vec::new(),
+        }
+    }
+}
+
+impl MemoryConfig {
+    fn validate_format(&self) -> Result<()> {
+        let mut rest = self.format.as_str();
+        while let Some(start) = rest.find('{') {
+            let end = match rest[start..].find('}') {
+                Some(end) => start + end,
+                None => bail!("unclosed `{{` in `memory.format`"),
+            };
+            let name = &rest[start + 1..end];
+            if !MEMORY_PLACEHOLDERS.contains(&name) {
+                bail!("unknown placeholder `{{{}}}` in `memory.format`", name);
+            }
+            rest = &rest[end + 1..];
+        }
+        Ok(())
+    }
+}
+
+/// Which space of the disk to show.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DiskShow {
+    Free,
+    Used,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiskConfig {
+    pub interval: Interval,
+    /// Mount points, each shown as a block. Hidden while not mounted.
+    pub mounts: Vec<String>,
+    pub show: DiskShow,
+    pub color: ColorConfig,
+    /// Limits of the used space in percentage
+    pub thresholds: Vec<Threshold>,
+}
+
+impl Default for DiskConfig {
+    fn default() -> Self {
+        Self {
+            interval: Interval::default(),
+            mounts: vec!["/".to_string()],
+            show: DiskShow::Free,
+            color: ColorConfig::foreground(FOREGROUND),
+            thresholds: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkConfig {
+    pub interval: Interval,
+    /// Interface to measure, the interface of the default route if unset
+    pub interface: Option<String>,
+    pub color: ColorConfig,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            interval: Interval::default(),
+            interface: None,
+            color: ColorConfig::foreground(FOREGROUND),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct IpConfig {
+    /// Delay before reconnecting when the netlink socket is lost
+    pub interval: Interval,
+    /// Interfaces to show in order, every interface but loopback if empty
+    pub interfaces: Vec<String>,
+    /// Show the interfaces without carrier or set down, which are hidden by default
+    pub show_down: bool,
+    pub ipv6: bool,
+    pub color: ColorConfig,
+}
+
+impl Default for IpConfig {
+    fn default() -> Self {
+        Self {
+            interval: Interval::default(),
+            interfaces: Vec::new(),
+            show_down: false,
+            ipv6: true,
+            color: ColorConfig::foreground(FOREGROUND),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct TemperatureConfig {
+    pub interval: Interval,
+    /// Sensor to show, like `coretemp` or `coretemp/Package id 0`. The highest
+    /// temperature of all the sensors if unset.
+    pub sensor: Option<String>,
+    pub color: ColorConfig,
+    /// Limits in °C
+    pub thresholds: Vec<Threshold>,
+}
+
+impl Default for TemperatureConfig {
+    fn default() -> Self {
+        Self {
+            interval: Interval::default(),
+            sensor: None,
+            color: ColorConfig::foreground(FOREGROUND),
+            thresholds: Vec::new(),
+        }
+    }
+}
+
+/// How to show the batteries when there are more than one.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BatteryMode {
+    /// A single block with the percentage weighted by the battery energy
+    Combined,
+    /// One block for each battery
+    Separate,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BatteryConfig {
+    pub interval: Interval,
+    pub mode: BatteryMode,
+    /// Number of samples the remaining time is averaged over, 0 to hide the time
+    pub estimate_window: usize,
+    pub color: ColorConfig,
+    pub status_color: StatusColor,
+    pub thresholds: Vec<Threshold>,
+    pub notification: BatteryNotification,
+}
+
+/// Desktop notifications when discharging below the percentages.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BatteryNotification {
+    pub enable: bool,
+    pub low: i32,
+    pub critical: i32,
+}
+
+impl Default for BatteryNotification {
+    fn default() -> Self {
+        Self {
+            enable: true,
+            low: 15,
+            critical: 5,
+        }
+    }
+}
+
+/// Icon color of each battery status, unset colors fallback to `color.icon_fg`.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct StatusColor {
+    pub charging: Option<Rgba>,
+    pub discharging: Option<Rgba>,
+    pub full: Option<Rgba>,
+    pub not_charging: Option<Rgba>,
+    pub unknown: Option<Rgba>,
+}
+
+impl Default for StatusColor {
+    fn default() -> Self {
+        Self {
+            charging: Some(Rgba::rgb(0xA3, 0xBE, 0x8C)),
+            discharging: None,
+            full: Some(Rgba::rgb(0x88, 0xC0, 0xD0)),
+            not_charging: Some(Rgba::rgb(0xEB, 0xCB, 0x8B)),
+            unknown: Some(Rgba::rgb(0x80, 0x80, 0x80)),
+        }
+    }
+}
+
+impl Default for BatteryConfig {
+    fn default() -> Self {
+        Self {
+            interval: Interval::default(),
+            mode: BatteryMode::Combined,
+            estimate_window: 10,
+            color: ColorConfig::foreground(FOREGROUND),
+            status_color: StatusColor::default(),
+            thresholds: Vec::new(),
+            notification: BatteryNotification::default(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct DateConfig {
+    /// The clock ticks on the multiple of the interval, so the default value makes it
+    /// change on minute boundaries.
+    pub interval: Interval,
+    /// strftime like format string, see the chrono document for all the specifiers
+    pub format: String,
+    pub color: ColorConfig,
+}
+
+impl Default for DateConfig {
+    fn default() -> Self {
+        Self {
+            interval: Interval::default(),
+            format: "%B/%d %I:%M %p".to_string(),
+            color: ColorConfig::foreground(FOREGROUND),
+        }
+    }
+}
+
+impl Config {
+    /// Load configuration from the given path. When path is None, try the default path
+    /// and fallback to the default configuration if the file doesn't exist.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match default_path() {
+                Some(path) if path.exists() => path,
+                _ => {
+                    tracing::info!("No configuration file found, using the default settings");
+                    return Ok(Self::default());
+                }
+            },
+        };
+
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Fail to read config file {}", path.display()))?;
+        let config = Self::parse(&content)
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+
+        tracing::info!("Configuration loaded from {}", path.display());
+        Ok(config)
+    }
+
+    /// Parse and validate the configuration from TOML text.
+    pub fn parse(content: &str) -> Result<Self> {
+        let config: Self = toml::from_str(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<()> {
+        #[cfg(not(feature = "bluetooth-battery"))]
+        if self.blocks.contains(&BlockKind::HeadsetBattery) {
+            bail!("block `headset_battery` requires the `bluetooth-battery` feature");
+        }
+
+        validate_block("song", self.song.interval, &[])?;
+        validate_block("volume", self.volume.interval, &self.volume.thresholds)?;
+        validate_block(
+            "headset_battery",
+            self.headset_battery.interval,
+            &self.headset_battery.thresholds,
+        )?;
+        validate_block("battery", self.battery.interval, &self.battery.thresholds)?;
+        validate_block("cpu", self.cpu.interval, &self.cpu.thresholds)?;
+        validate_block(
+            "cpu_freq",
+            self.cpu_freq.interval,
+            &self.cpu_freq.thresholds,
+        )?;
+        validate_block("memory", self.memory.interval, &self.memory.thresholds)?;
+        validate_block("disk", self.disk.interval, &self.disk.thresholds)?;
+        validate_block("network", self.network.interval, &[])?;
+        validate_block("wifi", self.wifi.interval, &self.wifi.thresholds)?;
+        validate_block("ip", self.ip.interval, &[])?;
+        validate_block(
+            "temperature",
+            self.temperature.interval,
+            &self.temperature.thresholds,
+        )?;
+        validate_block("date", self.date.interval, &[])?;
+
+        let notification = &self.battery.notification;
+        if notification.critical > notification.low {
+            bail!("`battery.notification.critical` should not be greater than `low`");
+        }
+        if self.cpu.per_core && self.cpu.history > 0 {
+            bail!("`cpu.per_core` and `cpu.history` can't be enabled together");
+        }
+        self.memory.validate_format()?;
+        if let Some(mount) = self.disk.mounts.iter().find(|m| !m.starts_with('/')) {
+            bail!("`disk.mounts` should be absolute paths, got `{}`", mount);
+        }
+        if StrftimeItems::new(&self.date.format).any(|item| item == Item::Error) {
+            bail!(
+                "invalid date format `{}` for `date.format`",
+                self.date.format
+            );
+        }
+
+        Ok(())
+    }
+}
+
+fn default_path() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_dir.join("dwm-bar").join("config.toml"))
+}
+
+#[test]
+fn test_parse_config() {
+    let config = Config::parse(
+        r##"
+        divider = " | "
+        blocks = ["cpu", "date"]
+
+        [cpu]
+        interval = 2
+        per_core = true
+        color = { text_fg = "#FFFFFF" }
+
+        [date]
+        format = "%H:%M"
+        "##,
+    )
+    .unwrap();
+
+    assert_eq!(config.divider, " | ");
+    assert_eq!(config.reset, "^d^");
+    assert_eq!(config.blocks, vec![BlockKind::Cpu, BlockKind::Date]);
+    assert_eq!(config.cpu.interval.or(10), Duration::from_secs(2));
+    assert!(config.cpu.per_core);
+    assert_eq!(config.battery.interval, Interval::default());
+    assert_eq!(config.battery.mode, BatteryMode::Combined);
+    assert_eq!(config.cpu.color.text_fg, Some(Rgba::rgb(0xFF, 0xFF, 0xFF)));
+    assert_eq!(config.cpu.color.icon_fg, None);
+    assert_eq!(config.date.format, "%H:%M");
+    assert_eq!(config.song, SongConfig::default());
+
+    let err = Config::parse("[disk]\ninterval = 0").unwrap_err();
+    assert!(err.to_string().contains("`disk.interval`"));
+
+    let err = Config::parse("[cpu]\nintervals = 2").unwrap_err();
+    assert!(err.to_string().contains("unknown field `intervals`"));
+
+    let err = Config::parse(r##"blocks = ["clock"]"##).unwrap_err();
+    assert!(err.to_string().contains("unknown variant `clock`"));
+
+    let err = Config::parse("[battery.color]\ntext_fg = \"#EAEAE\"").unwrap_err();
+    assert!(err.to_string().contains("invalid color `#EAEAE`"));
+
+    let config = Config::parse(
+        r##"
+        [urgent]
+        bg = "#D08770"
+
+        [battery]
+        mode = "separate"
+
+        [[battery.thresholds]]
+        below = 20
+        color = { text_bg = "#BF616A" }
+        urgent = true
+        "##,
+    )
+    .unwrap();
+    assert_eq!(config.urgent.fg, UrgentColor::default().fg);
+    assert_eq!(config.urgent.bg, Rgba::rgb(0xD0, 0x87, 0x70));
+    assert_eq!(config.battery.mode, BatteryMode::Separate);
+    let threshold = &config.battery.thresholds[0];
+    assert!(threshold.matches(19.0) && !threshold.matches(20.0));
+    assert!(threshold.urgent);
+
+    let err = Config::parse("[cpu]\nper_core = true\nhistory = 10").unwrap_err();
+    assert!(err.to_string().contains("`cpu.history`"));
+
+    let err = Config::parse("[[cpu.thresholds]]\nurgent = true").unwrap_err();
+    assert!(err.to_string().contains("`cpu.thresholds`"));
+
+    let err = Config::parse("[memory]\nformat = \"{mem_free}\"").unwrap_err();
+    assert!(err.to_string().contains("`{mem_free}`"));
+
+    let err = Config::parse("[disk]\nmounts = [\"home\"]").unwrap_err();
+    assert!(err.to_string().contains("`home`"));
+
+    let err = Config::parse("[date]\nformat = \"%Q\"").unwrap_err();
+    assert!(err.to_string().contains("date.format"));
+}
@@ -1,21 +1,15 @@
+mod bar;
 mod component;
-
-/// Reset the color the SchemeNorm
-static NORMAL_COLOR: &str = "^d^";
-static DIVIDER: &str = "     |     ";
+mod config;
+mod output;
 
 use anyhow::Result;
 use argh::FromArgs;
-use std::{process::Command, sync::Arc, time::Duration};
-use tokio::spawn as t_spawn;
-
-#[cfg(feature = "bluetooth-battery")]
-use tokio::time::interval;
-#[cfg(feature = "bluetooth-battery")]
-use tokio::sync::watch;
-
-use tokio::time::sleep;
-use tracing::{info, Level};
+use component::{Notifier, Registry, Session, SysRoot};
+use config::{BlockKind, Config};
+use output::OutputKind;
+use std::path::PathBuf;
+use tracing::{error, info, Level};
 use tracing_subscriber::FmtSubscriber;
 
 #[derive(FromArgs)]
@@ -26,105 +20,128 @@ struct App {
     once: bool,
 
     #[argh(switch)]
-    /// do not output contents to the bar, print it to stdout instead
+    /// do not output contents to the bar, print it to stdout instead. Same as `--output stdout`
     dry: bool,
-}
-
-#[cfg(feature = "bluetooth-battery")]
-use component::Block;
 
-#[cfg(feature = "bluetooth-battery")]
-async fn function(tx: watch::Sender<Option<Block>>, secs: u64) -> Result<()> {
-    let mut headset_battery = component::HeadsetBattery::new().await?;
+    #[argh(option, default = "OutputKind::Dwm")]
+    /// where to draw the bar: dwm, stdout, i3bar, lemonbar or tmux. Default to dwm
+    output: OutputKind,
 
-    let mut ticker = interval(Duration::from_secs(secs));
-    loop {
-        if tx.is_closed() {
-            break;
-        }
-
-        let block = headset_battery.update().await;
-        tx.send(block)?;
-
-        ticker.tick().await;
-    }
-
-    Ok(())
+    #[argh(option)]
+    /// path to the configuration file, default to ~/.config/dwm-bar/config.toml
+    config: Option<PathBuf>,
 }
 
-async fn run(app: &App) -> Result<()> {
-    info!("Entering information fetching loop");
+/// Register all the components. The order of registration is the order on the bar.
+async fn registry(config: &Config) -> Result<Registry> {
+    let mut registry = Registry::new();
+    let root = SysRoot::default();
+    let mut session = Session::default();
 
-    let song_info = Arc::new(component::SongInfo::new()?);
-
-    #[cfg(feature = "bluetooth-battery")]
-    let (tx, rx) = watch::channel(None);
-    #[cfg(feature = "bluetooth-battery")]
-    t_spawn(async move {
-        function(tx, 10).await.unwrap();
-    });
-
-    loop {
-        let song = song_info.clone();
-        #[cfg(feature = "bluetooth-battery")]
-        let mut btbat_rx = rx.clone();
-        let bar = vec![
-            t_spawn(async move { song.song_info().await }),
-            t_spawn(async { component::sound_volume().await }),
+    for kind in &config.blocks {
+        match kind {
+            BlockKind::Song => {
+                registry.register(component::SongInfo::new(&config.song, session.get()?));
+            }
+            BlockKind::Volume => {
+                registry.register(component::Volume::new(&config.volume));
+            }
             #[cfg(feature = "bluetooth-battery")]
-            t_spawn(async move {
-                if btbat_rx.changed().await.is_ok() {
-                    (*btbat_rx.borrow()).clone()
+            BlockKind::HeadsetBattery => {
+                match component::HeadsetBattery::new(&config.headset_battery).await {
+                    Ok(headset_battery) => {
+                        registry.register(headset_battery);
+                    }
+                    Err(e) => tracing::warn!("Headset battery component is disabled: {}", e),
+                }
+            }
+            // rejected when validating the configuration
+            #[cfg(not(feature = "bluetooth-battery"))]
+            BlockKind::HeadsetBattery => unreachable!(),
+            BlockKind::Battery => {
+                let notifier = if config.battery.notification.enable {
+                    match session.get() {
+                        Ok(conn) => Some(Notifier::new(conn)),
+                        Err(e) => {
+                            tracing::warn!("Low battery notification is disabled: {}", e);
+                            None
+                        }
+                    }
                 } else {
                     None
+                };
+                let batteries =
+                    component::Battery::discover(&config.battery, &root, notifier).await;
+                for battery in batteries {
+                    registry.register(battery);
                 }
-            }),
-            t_spawn(async { component::battery().await }),
-            t_spawn(async { component::avg_load().await }),
-            t_spawn(async { component::date_and_time() }),
-        ];
-
-        let mut info = Vec::new();
-        for task in bar {
-            let i = task.await.unwrap();
-            info.push(i);
-        }
-
-        let mut begining = true;
-        let mut barline = String::new();
-        for component in info.iter().flatten() {
-            if begining {
-                begining = false;
-            } else {
-                barline.push_str(DIVIDER);
             }
-            barline.push_str(&format!("{}", component));
-            barline.push_str(NORMAL_COLOR);
+            BlockKind::Cpu => {
+                registry.register(component::AvgLoad::new(&config.cpu, &root).await);
+            }
+            BlockKind::CpuFreq => {
+                registry.register(component::CpuFreq::new(&config.cpu_freq, &root));
+            }
+            BlockKind::Memory => {
+                registry.register(component::Memory::new(&config.memory, &root));
+            }
+            BlockKind::Disk => {
+                for mount in &config.disk.mounts {
+                    registry.register(component::Disk::new(&config.disk, mount, &root));
+                }
+            }
+            BlockKind::Network => {
+                registry.register(component::Network::new(&config.network, &root));
+            }
+            BlockKind::Wifi => match component::system_bus() {
+                Ok(conn) => {
+                    registry.register(component::Wifi::new(&config.wifi, conn));
+                }
+                Err(e) => tracing::warn!("Wi-Fi component is disabled: {}", e),
+            },
+            BlockKind::Ip => {
+                registry.register(component::IpAddress::new(&config.ip));
+            }
+            BlockKind::Temperature => {
+                registry.register(component::Temperature::new(&config.temperature, &root));
+            }
+            BlockKind::Date => {
+                registry.register(component::DateTime::new(&config.date));
+            }
         }
+    }
 
-        if !app.dry {
-            // Clean the bar
-            let mut cmd = Command::new("xsetroot");
-            let _hold = cmd
-                .arg("-name")
-                .arg("''")
-                .output()
-                .expect("Fail to execute xsetroot");
-            let _hold = cmd
-                .arg("-name")
-                .arg(barline)
-                .output()
-                .expect("Fail to execute xsetroot");
-        } else {
-            info!("New output: {}", barline);
-        }
+    Ok(registry)
+}
+
+async fn run(app: &App) -> Result<()> {
+    let config = Config::load(app.config.as_deref())?;
+    let registry = registry(&config).await?;
+
+    info!("Entering information fetching loop");
+
+    let kind = if app.dry {
+        OutputKind::Stdout
+    } else {
+        app.output
+    };
+    let mut output = output::new(kind, &config);
+
+    let (mut bar, mut updates) = bar::schedule(registry);
+    while let Some((id, content)) = updates.recv().await {
+        let changed = bar.update(id, content);
 
         if app.once {
-            return Ok(());
+            // wait until every component is updated
+            if bar.is_complete() {
+                return output.render(&bar.blocks());
+            }
+        } else if changed {
+            output.render(&bar.blocks())?;
         }
-
-        sleep(Duration::from_secs(10)).await;
     }
+
+    Ok(())
 }
 
 #[tokio::main]
@@ -132,10 +149,14 @@ async fn main() {
     let subscriber = FmtSubscriber::builder()
         .with_max_level(Level::TRACE)
         .without_time()
+        .with_writer(std::io::stderr)
         .finish();
 
     tracing::subscriber::set_global_default(subscriber).expect("Fail to set default logger");
 
     let app: App = argh::from_env();
-    run(&app).await.unwrap();
+    if let Err(e) = run(&app).await {
+        error!("{:?}", e);
+        std::process::exit(1);
+    }
 }
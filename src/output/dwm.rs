@@ -0,0 +1,278 @@
+use super::Output;
+use crate::component::{Block, Rgba};
+use crate::config::UrgentColor;
+use anyhow::{Context, Result};
+use tracing::{info, warn};
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{AtomEnum, PropMode, Window};
+use x11rb::rust_connection::RustConnection;
+use x11rb::wrapper::ConnectionExt as _;
+
+x11rb::atom_manager! {
+    Atoms: AtomsCookie {
+        UTF8_STRING,
+        _NET_WM_NAME,
+    }
+}
+
+struct X11 {
+    conn: RustConnection,
+    root: Window,
+    atoms: Atoms,
+}
+
+impl X11 {
+    fn connect(display: Option<&str>) -> Result<Self> {
+        let (conn, screen) = x11rb::connect(display).context("Fail to connect to X server")?;
+        let root = conn.setup().roots[screen].root;
+        let atoms = Atoms::new(&conn)?.reply()?;
+        Ok(Self { conn, root, atoms })
+    }
+
+    fn set_name(&self, name: &str) -> Result<()> {
+        for property in [AtomEnum::WM_NAME.into(), self.atoms._NET_WM_NAME] {
+            self.conn
+                .change_property8(
+                    PropMode::REPLACE,
+                    self.root,
+                    property,
+                    self.atoms.UTF8_STRING,
+                    name.as_bytes(),
+                )?
+                .check()?;
+        }
+        Ok(())
+    }
+}
+
+/// Draw the bar by setting the name of the root window, which is read by dwm.
+/// The connection to the X server is kept, and re-established when it is lost.
+pub struct Dwm {
+    display: Option<String>,
+    x11: Option<X11>,
+    divider: String,
+    reset: String,
+    urgent: UrgentColor,
+}
+
+impl Dwm {
+    /// Connect to the given display, or `$DISPLAY` if None.
+    pub fn new(display: Option<String>, divider: &str, reset: &str, urgent: &UrgentColor) -> Self {
+        Self {
+            display,
+            x11: None,
+            divider: divider.to_string(),
+            reset: reset.to_string(),
+            urgent: urgent.clone(),
+        }
+    }
+
+    fn format(&self, blocks: &[&Block]) -> String {
+        blocks
+            .iter()
+            .map(|block| format!("{}{}", status2d(block, &self.urgent), self.reset))
+            .collect::<Vec<_>>()
+            .join(&self.divider)
+    }
+
+    pub fn set_name(&mut self, name: &str) -> Result<()> {
+        if let Some(x11) = &self.x11 {
+            match x11.set_name(name) {
+                Ok(()) => return Ok(()),
+                Err(e) => {
+                    // the X server may be restarted, try again with a new connection
+                    warn!("Fail to set root window name: {}", e);
+                    self.x11 = None;
+                }
+            }
+        }
+
+        info!("Connecting to X server");
+        let x11 = X11::connect(self.display.as_deref())?;
+        x11.set_name(name)?;
+        self.x11 = Some(x11);
+        Ok(())
+    }
+}
+
+/// Size of the status2d graph in pixels, fits the default dwm bar with a 10pt font
+const GRAPH_HEIGHT: usize = 12;
+const GRAPH_TOP: usize = 3;
+const GRAPH_BAR_WIDTH: usize = 3;
+const GRAPH_GAP: usize = 1;
+
+/// Draw the values as bars with the rectangle escape `^rx,y,w,h^`, then move forward
+/// with `^fw^` to put the following text after the graph.
+fn status2d_graph(values: &[f32]) -> String {
+    let step = GRAPH_BAR_WIDTH + GRAPH_GAP;
+    let mut graph: String = values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            // keep at least 1 pixel to show an idle bar
+            let height = (value.clamp(0.0, 1.0) * GRAPH_HEIGHT as f32).round() as usize;
+            let height = height.max(1);
+            format!(
+                "^r{},{},{},{}^",
+                i * step,
+                GRAPH_TOP + GRAPH_HEIGHT - height,
+                GRAPH_BAR_WIDTH,
+                height
+            )
+        })
+        .collect();
+    graph += &format!("^f{}^", values.len() * step);
+    graph
+}
+
+/// Render the block with the status2d color escapes `^c#RRGGBB^` and `^b#RRGGBB^`.
+/// status2d has no urgency marker, so urgent blocks are drawn in the urgent colors,
+/// unless their thresholds set the colors.
+pub(crate) fn status2d(block: &Block, urgent: &UrgentColor) -> String {
+    let escape = |fg: Option<Rgba>, bg: Option<Rgba>| {
+        let (fg, bg) = if block.is_urgent() && !block.has_threshold_color() {
+            (Some(urgent.fg), Some(urgent.bg))
+        } else {
+            (fg, bg)
+        };
+        let escape = |kind: char, color: Option<Rgba>| match color {
+            Some(color) => format!("^{}{}^", kind, color.rgb_hex()),
+            None => String::new(),
+        };
+        escape('c', fg) + &escape('b', bg)
+    };
+
+    let color = block.colors();
+    // [icon] [text] [graph]
+    let mut line = format!(
+        "{}{} {}{}",
+        escape(color.icon_fg, color.icon_bg),
+        block.icon(),
+        escape(color.fg, color.bg),
+        block.text()
+    );
+    if !block.graph_values().is_empty() {
+        line += " ";
+        line += &status2d_graph(block.graph_values());
+    }
+    line
+}
+
+impl Output for Dwm {
+    fn render(&mut self, blocks: &[&Block]) -> Result<()> {
+        let barline = self.format(blocks);
+        if let Err(e) = self.set_name(&barline) {
+            // not fatal, try again in next update
+            tracing::error!("Fail to draw the bar: {:?}", e);
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_status2d_graph() {
+    let block = Block::new("C", "50 %").graph(vec![0.0, 0.5, 1.0]);
+    assert_eq!(
+        status2d(&block, &UrgentColor::default()),
+        "C 50 % ^r0,14,3,1^^r4,9,3,6^^r8,3,3,12^^f12^"
+    );
+}
+
+#[test]
+fn test_status2d_urgent() {
+    use crate::config::{ColorConfig, Threshold};
+
+    let thresholds = [
+        Threshold {
+            below: Some(10.0),
+            color: ColorConfig {
+                text_fg: Some(Rgba::rgb(0xBF, 0x61, 0x6A)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        Threshold {
+            below: Some(5.0),
+            urgent: true,
+            ..Default::default()
+        },
+    ];
+    // the threshold colors are kept
+    let block = Block::new("B", "3 %").threshold(3.0, &thresholds);
+    assert_eq!(status2d(&block, &UrgentColor::default()), "B ^c#BF616A^3 %");
+
+    let block = Block::new("B", "3 %").threshold(3.0, &thresholds[1..]);
+    assert_eq!(
+        status2d(&block, &UrgentColor::default()),
+        "^c#EAEAEA^^b#BF616A^B ^c#EAEAEA^^b#BF616A^3 %"
+    );
+}
+
+#[test]
+fn test_format() {
+    use crate::config::ColorConfig;
+
+    // no X connection until the name is set
+    let dwm = Dwm::new(None, " | ", "^d^", &UrgentColor::default());
+    let volume = Block::new("V", "50 %").color(&ColorConfig {
+        text_fg: Some(Rgba::rgb(0xEA, 0xEA, 0xEA)),
+        ..Default::default()
+    });
+    let date = Block::new("D", "12:00");
+    assert_eq!(
+        dwm.format(&[&volume, &date]),
+        "V ^c#EAEAEA^50 %^d^ | D 12:00^d^"
+    );
+    assert_eq!(dwm.format(&[]), "");
+}
+
+#[test]
+#[ignore = "needs Xvfb"]
+fn test_set_name_on_xvfb() {
+    use std::process::{Child, Command, Stdio};
+    use std::time::Duration;
+    use x11rb::protocol::xproto::ConnectionExt;
+
+    fn xvfb(display: &str) -> Option<Child> {
+        let child = Command::new("Xvfb")
+            .arg(display)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        std::thread::sleep(Duration::from_secs(1));
+        Some(child)
+    }
+
+    fn get_name(display: &str, property: &str) -> String {
+        let (conn, screen) = x11rb::connect(Some(display)).unwrap();
+        let root = conn.setup().roots[screen].root;
+        let atom = conn.intern_atom(false, property.as_bytes()).unwrap();
+        let atom = atom.reply().unwrap().atom;
+        let reply = conn
+            .get_property(false, root, atom, AtomEnum::ANY, 0, u32::MAX)
+            .unwrap()
+            .reply()
+            .unwrap();
+        String::from_utf8(reply.value).unwrap()
+    }
+
+    let display = format!(":{}", 90 + std::process::id() % 100);
+    let mut server = xvfb(&display).expect("Xvfb is not installed");
+
+    let mut dwm = Dwm::new(Some(display.clone()), " | ", "^d^", &UrgentColor::default());
+    dwm.set_name("音乐 | 50 %").unwrap();
+    assert_eq!(get_name(&display, "WM_NAME"), "音乐 | 50 %");
+    assert_eq!(get_name(&display, "_NET_WM_NAME"), "音乐 | 50 %");
+
+    // restart the X server, the connection should be re-established
+    server.kill().unwrap();
+    server.wait().unwrap();
+    let mut server = xvfb(&display).unwrap();
+
+    dwm.set_name("restarted").unwrap();
+    assert_eq!(get_name(&display, "WM_NAME"), "restarted");
+
+    server.kill().unwrap();
+    server.wait().unwrap();
+}
@@ -0,0 +1,84 @@
+use super::{print_line, Output};
+use crate::component::Block;
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+
+/// Print the bar in the i3bar protocol: a header, followed by an infinite JSON array
+/// of status lines. Works with i3bar and swaybar.
+pub struct I3bar {
+    started: bool,
+}
+
+impl I3bar {
+    pub fn new() -> Self {
+        Self { started: false }
+    }
+
+    fn format(blocks: &[&Block]) -> Value {
+        let blocks = blocks
+            .iter()
+            .map(|block| {
+                let color = block.colors();
+                let mut object = Map::new();
+                object.insert("full_text".to_string(), json!(block.to_string()));
+                // i3bar only support one color set for each block
+                if let Some(fg) = color.fg.or(color.icon_fg) {
+                    object.insert("color".to_string(), json!(fg.to_string()));
+                }
+                if let Some(bg) = color.bg.or(color.icon_bg) {
+                    object.insert("background".to_string(), json!(bg.to_string()));
+                }
+                if block.is_urgent() {
+                    object.insert("urgent".to_string(), json!(true));
+                }
+                Value::Object(object)
+            })
+            .collect();
+        Value::Array(blocks)
+    }
+}
+
+impl Output for I3bar {
+    fn render(&mut self, blocks: &[&Block]) -> Result<()> {
+        let status = Self::format(blocks).to_string();
+        if self.started {
+            print_line(&format!(",{}", status))
+        } else {
+            self.started = true;
+            print_line(&json!({ "version": 1 }).to_string())?;
+            print_line("[")?;
+            print_line(&status)
+        }
+    }
+}
+
+#[test]
+fn test_i3bar_format() {
+    use crate::component::Rgba;
+    use crate::config::{ColorConfig, Threshold};
+
+    let color = ColorConfig {
+        text_fg: Some(Rgba::rgb(0xEA, 0xEA, 0xEA)),
+        icon_bg: Some(Rgba::new(0x0C, 0x0C, 0x0C, 0x80)),
+        ..Default::default()
+    };
+    let cpu = Block::new("C", "2.00 %").color(&color);
+    let date = Block::new("D", "\"12:00\"");
+    let battery = Block::new("B", "5 %").threshold(
+        5.0,
+        &[Threshold {
+            below: Some(10.0),
+            urgent: true,
+            ..Default::default()
+        }],
+    );
+
+    assert_eq!(
+        I3bar::format(&[&cpu, &date]).to_string(),
+        r##"[{"background":"#0C0C0C80","color":"#EAEAEA","full_text":"C 2.00 %"},{"full_text":"D \"12:00\""}]"##
+    );
+    assert_eq!(
+        I3bar::format(&[&battery]).to_string(),
+        r##"[{"full_text":"B 5 %","urgent":true}]"##
+    );
+}
@@ -0,0 +1,96 @@
+use super::{print_line, sparkline, Output};
+use crate::component::{Block, Rgba};
+use anyhow::Result;
+
+/// Print the bar for lemonbar, with `%{F#RRGGBB}` and `%{B#RRGGBB}` color escapes.
+/// Urgent blocks are drawn in reverse colors.
+pub struct Lemonbar {
+    divider: String,
+}
+
+impl Lemonbar {
+    pub fn new(divider: &str) -> Self {
+        Self {
+            divider: divider.to_string(),
+        }
+    }
+
+    fn format(&self, blocks: &[&Block]) -> String {
+        blocks
+            .iter()
+            .map(|block| {
+                let color = block.colors();
+                // `%{R}` swaps the colors until the next `%{R}`
+                let reverse = if block.is_urgent() { "%{R}" } else { "" };
+                format!(
+                    "{}{}{}{} {}{}{}{}{}%{{F-}}%{{B-}}",
+                    reverse,
+                    style('F', color.icon_fg),
+                    style('B', color.icon_bg),
+                    escape(block.icon()),
+                    style('F', color.fg),
+                    style('B', color.bg),
+                    escape(block.text()),
+                    sparkline(block),
+                    reverse,
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(&self.divider)
+    }
+}
+
+/// Lemonbar accepts colors in `#RRGGBB` or `#AARRGGBB` format
+fn style(kind: char, color: Option<Rgba>) -> String {
+    match color {
+        Some(color) if color.a != 0xFF => format!(
+            "%{{{}#{:02X}{}}}",
+            kind,
+            color.a,
+            color.rgb_hex().trim_start_matches('#')
+        ),
+        Some(color) => format!("%{{{}{}}}", kind, color.rgb_hex()),
+        None => String::new(),
+    }
+}
+
+/// `%` starts a formatting block in lemonbar
+fn escape(text: &str) -> String {
+    text.replace('%', "%%")
+}
+
+impl Output for Lemonbar {
+    fn render(&mut self, blocks: &[&Block]) -> Result<()> {
+        print_line(&self.format(blocks))
+    }
+}
+
+#[test]
+fn test_lemonbar_format() {
+    use crate::config::{ColorConfig, Threshold};
+
+    let color = ColorConfig {
+        text_fg: Some(Rgba::rgb(0xEA, 0xEA, 0xEA)),
+        icon_bg: Some(Rgba::new(0x0C, 0x0C, 0x0C, 0x80)),
+        ..Default::default()
+    };
+    let cpu = Block::new("C", "2.00 %").color(&color);
+    let date = Block::new("D", "12:00");
+    let battery = Block::new("B", "5 %").threshold(
+        5.0,
+        &[Threshold {
+            below: Some(10.0),
+            urgent: true,
+            ..Default::default()
+        }],
+    );
+
+    assert_eq!(
+        Lemonbar::new(" | ").format(&[&cpu, &date]),
+        "%{B#800C0C0C}C %{F#EAEAEA}2.00 %%%{F-}%{B-} | D 12:00%{F-}%{B-}"
+    );
+    assert_eq!(
+        Lemonbar::new(" | ").format(&[&battery]),
+        "%{R}B 5 %%%{R}%{F-}%{B-}"
+    );
+}
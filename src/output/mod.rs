@@ -0,0 +1,90 @@
+mod dwm;
+mod i3bar;
+mod lemonbar;
+mod stdout;
+mod tmux;
+
+// re-export
+pub use dwm::Dwm;
+pub use i3bar::I3bar;
+pub use lemonbar::Lemonbar;
+pub use stdout::Stdout;
+pub use tmux::Tmux;
+#[cfg(test)]
+pub(crate) use dwm::status2d;
+
+use crate::component::Block;
+use crate::config::Config;
+use anyhow::Result;
+use std::str::FromStr;
+
+/// A target to draw the bar on. Every output receives the same blocks, and renders
+/// them in its own protocol.
+pub trait Output {
+    /// Draw the visible blocks, from left to right.
+    fn render(&mut self, blocks: &[&Block]) -> Result<()>;
+}
+
+/// All the available outputs, selected by the `--output` flag.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputKind {
+    Dwm,
+    Stdout,
+    I3bar,
+    Lemonbar,
+    Tmux,
+}
+
+impl FromStr for OutputKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dwm" => Ok(Self::Dwm),
+            "stdout" => Ok(Self::Stdout),
+            "i3bar" => Ok(Self::I3bar),
+            "lemonbar" => Ok(Self::Lemonbar),
+            "tmux" => Ok(Self::Tmux),
+            _ => Err(format!(
+                "unknown output `{}`, expected one of dwm, stdout, i3bar, lemonbar, tmux",
+                s
+            )),
+        }
+    }
+}
+
+/// Build the output of the given kind.
+pub fn new(kind: OutputKind, config: &Config) -> Box<dyn Output> {
+    match kind {
+        OutputKind::Dwm => Box::new(Dwm::new(
+            None,
+            &config.divider,
+            &config.reset,
+            &config.urgent,
+        )),
+        OutputKind::Stdout => Box::new(Stdout::new(&config.divider)),
+        OutputKind::I3bar => Box::new(I3bar::new()),
+        OutputKind::Lemonbar => Box::new(Lemonbar::new(&config.divider)),
+        OutputKind::Tmux => Box::new(Tmux::new(&config.divider)),
+    }
+}
+
+/// Print a line to stdout, and flush it immediately for the reader.
+fn print_line(line: &str) -> Result<()> {
+    use std::io::Write;
+
+    let mut stdout = std::io::stdout().lock();
+    writeln!(stdout, "{}", line)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Graph of the block in Unicode blocks after the text, for the outputs without
+/// drawing support.
+fn sparkline(block: &Block) -> String {
+    if block.graph_values().is_empty() {
+        String::new()
+    } else {
+        format!(" {}", block.sparkline())
+    }
+}
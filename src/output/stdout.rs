@@ -0,0 +1,30 @@
+use super::{print_line, Output};
+use crate::component::Block;
+use anyhow::Result;
+
+/// Print the bar as plain text to stdout, one line per update.
+pub struct Stdout {
+    divider: String,
+}
+
+impl Stdout {
+    pub fn new(divider: &str) -> Self {
+        Self {
+            divider: divider.to_string(),
+        }
+    }
+
+    fn format(&self, blocks: &[&Block]) -> String {
+        blocks
+            .iter()
+            .map(|block| block.to_string())
+            .collect::<Vec<_>>()
+            .join(&self.divider)
+    }
+}
+
+impl Output for Stdout {
+    fn render(&mut self, blocks: &[&Block]) -> Result<()> {
+        print_line(&self.format(blocks))
+    }
+}
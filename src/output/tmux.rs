@@ -0,0 +1,93 @@
+use super::{print_line, sparkline, Output};
+use crate::component::{Block, Rgba};
+use anyhow::Result;
+
+/// Print the bar for the tmux status line, with `#[fg=#RRGGBB,bg=#RRGGBB]` styles.
+/// Use it with `--once` in the `status-right` option. Urgent blocks are drawn in
+/// reverse colors.
+pub struct Tmux {
+    divider: String,
+}
+
+impl Tmux {
+    pub fn new(divider: &str) -> Self {
+        Self {
+            divider: divider.to_string(),
+        }
+    }
+
+    fn format(&self, blocks: &[&Block]) -> String {
+        blocks
+            .iter()
+            .map(|block| {
+                let color = block.colors();
+                format!(
+                    "{}{}{} {}{}{}#[default]",
+                    if block.is_urgent() { "#[reverse]" } else { "" },
+                    style(color.icon_fg, color.icon_bg),
+                    escape(block.icon()),
+                    style(color.fg, color.bg),
+                    escape(block.text()),
+                    sparkline(block),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(&self.divider)
+    }
+}
+
+/// Tmux doesn't support transparency, the alpha channel is dropped
+fn style(fg: Option<Rgba>, bg: Option<Rgba>) -> String {
+    let styles = [
+        fg.map(|fg| format!("fg={}", fg.rgb_hex())),
+        bg.map(|bg| format!("bg={}", bg.rgb_hex())),
+    ];
+    let styles: Vec<_> = styles.into_iter().flatten().collect();
+    if styles.is_empty() {
+        String::new()
+    } else {
+        format!("#[{}]", styles.join(","))
+    }
+}
+
+/// `#` starts a style or a format in tmux
+fn escape(text: &str) -> String {
+    text.replace('#', "##")
+}
+
+impl Output for Tmux {
+    fn render(&mut self, blocks: &[&Block]) -> Result<()> {
+        print_line(&self.format(blocks))
+    }
+}
+
+#[test]
+fn test_tmux_format() {
+    use crate::config::{ColorConfig, Threshold};
+
+    let color = ColorConfig {
+        text_fg: Some(Rgba::rgb(0xEA, 0xEA, 0xEA)),
+        text_bg: Some(Rgba::new(0x17, 0x16, 0x17, 0x80)),
+        icon_fg: Some(Rgba::rgb(0xEA, 0xEA, 0xEA)),
+        ..Default::default()
+    };
+    let song = Block::new("S", "Track #1").color(&color);
+    let date = Block::new("D", "12:00");
+    let battery = Block::new("B", "5 %").threshold(
+        5.0,
+        &[Threshold {
+            below: Some(10.0),
+            urgent: true,
+            ..Default::default()
+        }],
+    );
+
+    assert_eq!(
+        Tmux::new(" | ").format(&[&song, &date]),
+        "#[fg=#EAEAEA]S #[fg=#EAEAEA,bg=#171617]Track ##1#[default] | D 12:00#[default]"
+    );
+    assert_eq!(
+        Tmux::new(" | ").format(&[&battery]),
+        "#[reverse]B 5 %#[default]"
+    );
+}